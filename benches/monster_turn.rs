@@ -0,0 +1,90 @@
+//! Measures the cost of a monster turn with a crowd of monsters all chasing
+//! the player. Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use rusty_roguelike::prelude::*;
+use rusty_roguelike::score_tracker::ScoreTracker;
+use rusty_roguelike::spawner::load_templates;
+
+const MONSTERS: usize = 60;
+const TURNS: u32 = 200;
+
+fn main() {
+    println!("{} monsters, average over {} turns", MONSTERS, TURNS);
+    println!("  player standing still: {:?} per turn", run(false));
+    println!("  player moving every turn: {:?} per turn", run(true));
+}
+
+fn run(player_moves: bool) -> Duration {
+    let mut rng = RandomNumberGenerator::seeded(31);
+    let map_builder = MapBuilder::new(&mut rng, 0);
+    let map = map_builder.map;
+    let player_start = map_builder.player_start;
+
+    let mut ecs = World::default();
+    spawn_player(&mut ecs, player_start);
+    <&mut Health>::query()
+        .filter(component::<Player>())
+        .for_each_mut(&mut ecs, |health| {
+            health.current = i32::MAX;
+            health.max = i32::MAX;
+        });
+
+    let mut floor: Vec<Point> = (0..map.tiles.len())
+        .map(|idx| map.index_to_point2d(idx))
+        .filter(|pt| map.can_enter_tile(*pt) && *pt != player_start)
+        .collect();
+    for _ in 0..MONSTERS {
+        let idx = rng.random_slice_index(&floor).unwrap();
+        let pos = floor.remove(idx);
+        let mut brain = Brain::new(Behaviour::Guard, pos, 0);
+        brain.state = AiState::Hunting {
+            last_known: player_start,
+        };
+        ecs.push((
+            Enemy,
+            pos,
+            Health {
+                current: 10,
+                max: 10,
+            },
+            FieldOfView::new(SCREEN_WIDTH),
+            brain,
+            Damage(0),
+        ));
+    }
+
+    let player_moves_to = map
+        .get_available_exits(map.point2d_to_index(player_start))
+        .first()
+        .map(|(idx, _)| map.index_to_point2d(*idx))
+        .unwrap_or(player_start);
+
+    let mut resources = Resources::default();
+    resources.insert(map);
+    resources.insert(Camera::new(player_start));
+    resources.insert(ScoreTracker::new());
+    resources.insert(FlowField::new());
+    resources.insert(load_templates());
+    resources.insert(rng);
+
+    let mut schedule = build_monster_ai_scheduler();
+    let mut elapsed = Duration::ZERO;
+    for turn in 0..TURNS {
+        if player_moves {
+            let destination = if turn % 2 == 0 {
+                player_moves_to
+            } else {
+                player_start
+            };
+            <&mut Point>::query()
+                .filter(component::<Player>())
+                .for_each_mut(&mut ecs, |pos| *pos = destination);
+        }
+        let start = Instant::now();
+        schedule.execute(&mut ecs, &mut resources);
+        elapsed += start.elapsed();
+    }
+    elapsed / TURNS
+}
@@ -0,0 +1,235 @@
+//! Generates levels without starting the game, writing each one out as
+//! ASCII art and as RON along with a few statistics, so that levels can be
+//! generated in bulk and inspected.
+//!
+//!     cargo run --bin mapgen -- --seed 7 --count 100 --level 1 --out maps
+//!
+//! `--theme` and `--architect` pick a particular theme or architect instead
+//! of a random one. In the ASCII art `@` is the player's start, `>` the
+//! stairs down, `"` the amulet and `k` the key. Planned spawns are `e` for
+//! enemies, `i` for items, `^` for traps, `l` for lights such as braziers,
+//! `n` for named templates and `s` for anything else.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use ron::ser::PrettyConfig;
+use rusty_roguelike::prelude::*;
+use rusty_roguelike::spawner::template::EntityType;
+use serde::Serialize;
+
+/// The level on which the amulet is placed rather than the stairs down.
+const AMULET_LEVEL: usize = 2;
+
+struct Options {
+    seed: u64,
+    count: u64,
+    level: usize,
+    theme: Option<String>,
+    architect: Option<String>,
+    out: PathBuf,
+}
+
+#[derive(Serialize)]
+struct LevelReport {
+    seed: u64,
+    level: usize,
+    width: i32,
+    height: i32,
+    start: (i32, i32),
+    exit: (i32, i32),
+    amulet: bool,
+    key: Option<(i32, i32)>,
+    floor_percent: f32,
+    path_length: Option<usize>,
+    problem: Option<String>,
+    spawns: Vec<SpawnReport>,
+    tiles: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SpawnReport {
+    x: i32,
+    y: i32,
+    entity: Option<EntityType>,
+    template: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: mapgen [--seed N] [--count N] [--level N] [--theme {}] [--architect {}] [--out DIR]",
+        THEME_NAMES.join("|"),
+        ARCHITECT_NAMES.join("|")
+    );
+    process::exit(1);
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        seed: 1,
+        count: 1,
+        level: 0,
+        theme: None,
+        architect: None,
+        out: PathBuf::from("mapgen"),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--seed" => options.seed = value.parse().unwrap_or_else(|_| usage()),
+            "--count" => options.count = value.parse().unwrap_or_else(|_| usage()),
+            "--level" => options.level = value.parse().unwrap_or_else(|_| usage()),
+            "--theme" if THEME_NAMES.contains(&value.as_str()) => options.theme = Some(value),
+            "--architect" if ARCHITECT_NAMES.contains(&value.as_str()) => {
+                options.architect = Some(value)
+            }
+            "--out" => options.out = PathBuf::from(value),
+            _ => usage(),
+        }
+    }
+    options
+}
+
+/// Builds a level the way the game would, including its vault or stairs,
+/// along with the reason it failed validation if it did.
+fn generate(options: &Options, seed: u64) -> (MapBuilder, Option<MapProblem>) {
+    let mut rng = RandomNumberGenerator::seeded(seed);
+    let mut mb = match (&options.theme, &options.architect) {
+        (None, None) => MapBuilder::new(&mut rng, options.level),
+        (theme, architect) => MapBuilder::with_theme(
+            &mut rng,
+            options.level,
+            theme_named(theme.as_deref().unwrap_or(THEME_NAMES[0])).unwrap(),
+            architect.as_deref(),
+        ),
+    };
+    let problem = mb.validate().err();
+    if options.level == AMULET_LEVEL {
+        mb.build_vault();
+    } else {
+        let exit_idx = mb.map.point2d_to_index(mb.amulet_start);
+        mb.map.tiles[exit_idx] = TileType::Exit;
+    }
+    (mb, problem)
+}
+
+fn report(mb: &MapBuilder, problem: Option<MapProblem>, seed: u64, level: usize) -> LevelReport {
+    let floor = mb
+        .map
+        .tiles
+        .iter()
+        .filter(|t| t.movement_cost().is_some())
+        .count();
+    LevelReport {
+        seed,
+        level,
+        width: mb.map.width,
+        height: mb.map.height,
+        start: mb.player_start.to_tuple(),
+        exit: mb.amulet_start.to_tuple(),
+        amulet: level == AMULET_LEVEL,
+        key: mb.key_start.map(|key| key.to_tuple()),
+        floor_percent: 100.0 * floor as f32 / mb.map.tiles.len() as f32,
+        path_length: mb.path_length(),
+        problem: problem.map(|problem| format!("{:?}", problem)),
+        spawns: mb
+            .spawn_locations
+            .iter()
+            .map(|spawn| SpawnReport {
+                x: spawn.point.x,
+                y: spawn.point.y,
+                entity: spawn.preferred_entity,
+                template: spawn.template.clone(),
+            })
+            .collect(),
+        tiles: mb.map.to_ascii().lines().map(String::from).collect(),
+    }
+}
+
+/// The map as text, with the start, exit, key and spawn plan drawn over it.
+fn ascii_art(mb: &MapBuilder, amulet: bool) -> String {
+    let mut rows: Vec<Vec<char>> = mb
+        .map
+        .to_ascii()
+        .lines()
+        .map(|row| row.chars().collect())
+        .collect();
+    let mut mark = |pt: Point, c: char| {
+        if mb.map.in_bounds(pt) {
+            rows[pt.y as usize][pt.x as usize] = c;
+        }
+    };
+    for spawn in mb.spawn_locations.iter() {
+        let c = match (spawn.preferred_entity, &spawn.template) {
+            (_, Some(_)) => 'n',
+            (Some(EntityType::Enemy), _) => 'e',
+            (Some(EntityType::Item), _) => 'i',
+            (Some(EntityType::Trap), _) => '^',
+            (Some(EntityType::Prop), _) => 'l',
+            (None, None) => 's',
+        };
+        mark(spawn.point, c);
+    }
+    if let Some(key) = mb.key_start {
+        mark(key, 'k');
+    }
+    mark(mb.amulet_start, if amulet { '"' } else { '>' });
+    mark(mb.player_start, '@');
+    rows.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+fn main() {
+    let options = parse_options();
+    if let Err(e) = fs::create_dir_all(&options.out) {
+        eprintln!("Unable to create {}: {}", options.out.display(), e);
+        process::exit(1);
+    }
+
+    let mut reports = Vec::new();
+    for seed in options.seed..options.seed + options.count {
+        let (mb, problem) = generate(&options, seed);
+        let report = report(&mb, problem, seed, options.level);
+        let name = format!("level{}_seed{}", options.level, seed);
+        let ron = ron::ser::to_string_pretty(&report, PrettyConfig::new())
+            .expect("Unable to serialize level report");
+        let written = fs::write(
+            options.out.join(format!("{}.txt", name)),
+            ascii_art(&mb, report.amulet),
+        )
+        .and_then(|_| fs::write(options.out.join(format!("{}.ron", name)), ron));
+        if let Err(e) = written {
+            eprintln!("Unable to write {}: {}", name, e);
+            process::exit(1);
+        }
+        println!(
+            "seed {:>6}: {}x{}, {:5.1}% floor, path {:>4}, {:>3} spawns{}",
+            seed,
+            report.width,
+            report.height,
+            report.floor_percent,
+            report
+                .path_length
+                .map_or_else(|| "-".to_string(), |length| length.to_string()),
+            report.spawns.len(),
+            report
+                .problem
+                .as_ref()
+                .map_or_else(String::new, |problem| format!(", {}", problem))
+        );
+        reports.push(report);
+    }
+
+    let generated = reports.len() as f32;
+    let paths: Vec<usize> = reports.iter().filter_map(|r| r.path_length).collect();
+    println!(
+        "{} levels: {:.1}% floor on average, average path {:.1}, {} failed validation",
+        reports.len(),
+        reports.iter().map(|r| r.floor_percent).sum::<f32>() / generated,
+        paths.iter().sum::<usize>() as f32 / paths.len().max(1) as f32,
+        reports.iter().filter(|r| r.problem.is_some()).count()
+    );
+}
@@ -2,6 +2,7 @@ use std::collections::HashSet;
 
 use bracket_lib::terminal::{ColorPair, FontCharType, Point};
 use legion::Entity;
+use serde::Deserialize;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Render {
@@ -17,9 +18,6 @@ pub struct Player {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Enemy;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct MovingRandomly;
-
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToMove {
     pub entity: Entity,
@@ -41,8 +39,139 @@ pub struct WantsToAttack {
     pub victim: Entity,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum Behaviour {
+    Guard,
+    Wanderer,
+    Sleeper,
+    Patroller,
+    Ranged { range: i32 },
+}
+
+/// The guardian of the amulet. It enrages once badly hurt, hitting harder
+/// and rousing every other monster on the level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Boss {
+    pub enraged: bool,
+}
+
+/// A special ability declared on a monster template. Each one becomes its
+/// own component when the monster is spawned.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub enum Ability {
+    PoisonOnHit { damage: i32, turns: i32 },
+    LifeDrain,
+    Regeneration(i32),
+    SummonMinions { minion: String, count: i32, cooldown: i32, limit: i32 },
+    BreathAttack { damage: i32, range: i32, cooldown: i32 },
+    SplitOnDeath { into: String, count: i32 },
+    Swimmer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoisonOnHit {
+    pub damage: i32,
+    pub turns: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Poisoned {
+    pub damage: i32,
+    pub turns: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LifeDrain;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Regenerates(pub i32);
+
+/// Keeps track of the minions a monster has called that are still alive, so
+/// that it never has more than `limit` of them at once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SummonsMinions {
+    pub minion: String,
+    pub count: i32,
+    pub cooldown: i32,
+    pub ready_in: i32,
+    pub limit: i32,
+    pub minions: Vec<Entity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BreathAttack {
+    pub damage: i32,
+    pub range: i32,
+    pub cooldown: i32,
+    pub ready_in: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplitsOnDeath {
+    pub into: String,
+    pub count: i32,
+}
+
+/// Can cross deep water.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swimmer;
+
+/// Wading through shallow water; the next attempt to move is spent
+/// struggling free instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slowed;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pack {
+    pub leader: Entity,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LootEntry {
+    pub item: String,
+    pub chance: i32,
+    pub quantity: (i32, i32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LootTable(pub Vec<LootEntry>);
+
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct ChasingPlayer;
+pub enum AiState {
+    Idle,
+    Sleeping,
+    Patrolling { origin: Point, target: Point },
+    Hunting { last_known: Point },
+    Fleeing,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Brain {
+    pub behaviour: Behaviour,
+    pub state: AiState,
+    pub flee_below: i32,
+}
+
+impl Brain {
+    pub fn new(behaviour: Behaviour, origin: Point, flee_below: i32) -> Self {
+        Self {
+            behaviour,
+            state: Self::resting_state(behaviour, origin),
+            flee_below,
+        }
+    }
+
+    pub fn resting_state(behaviour: Behaviour, position: Point) -> AiState {
+        match behaviour {
+            Behaviour::Sleeper => AiState::Sleeping,
+            Behaviour::Patroller => AiState::Patrolling {
+                origin: position,
+                target: position,
+            },
+            _ => AiState::Idle,
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Item;
@@ -50,6 +179,42 @@ pub struct Item;
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AmuletOfYala;
 
+/// What happens to the player on stepping onto a trap.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum TrapEffect {
+    Spikes { damage: i32 },
+    Teleport,
+    Alarm { radius: i32 },
+    Pit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trap {
+    pub effect: TrapEffect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SprungTrap {
+    pub victim: Entity,
+    pub trap: Entity,
+}
+
+/// Lights up the tiles it can see, brightest close by and fading to nothing
+/// at the edge of its range.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct LightSource {
+    pub color: (u8, u8, u8),
+    pub range: i32,
+}
+
+/// Not drawn or described until the player has found it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hidden;
+
+/// Opens a locked door, and is used up doing so.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Key;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FieldOfView {
     pub visible_tiles: HashSet<Point>,
@@ -107,3 +272,25 @@ pub struct ScreenEffects {
 pub enum ScreenEffectsEnum {
     TakeDamage
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resting;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutoExploring {
+    pub known_items: HashSet<Entity>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Travelling {
+    pub path: Vec<Point>,
+    pub known_enemies: HashSet<Entity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Looking {
+    pub cursor: Point,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Description(pub String);
@@ -0,0 +1,108 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RememberedEntity {
+    pub position: Point,
+    pub glyph: FontCharType,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EntityMemory {
+    remembered: HashMap<Entity, RememberedEntity>,
+}
+
+impl EntityMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets anything remembered on a tile that is now in view, then records
+    /// everything that can actually be seen there. An entity that has moved or
+    /// been removed is therefore only forgotten once the player looks again.
+    pub fn update(
+        &mut self,
+        visible_tiles: &HashSet<Point>,
+        seen: Vec<(Entity, RememberedEntity)>,
+    ) {
+        self.remembered
+            .retain(|_, memory| !visible_tiles.contains(&memory.position));
+        seen.into_iter().for_each(|(entity, memory)| {
+            self.remembered.insert(entity, memory);
+        });
+    }
+
+    pub fn remembered(&self) -> impl Iterator<Item = &RememberedEntity> {
+        self.remembered.values()
+    }
+
+    pub fn remembered_at(&self, position: Point) -> impl Iterator<Item = &RememberedEntity> {
+        self.remembered
+            .values()
+            .filter(move |memory| memory.position == position)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    fn goblin_at(position: Point) -> RememberedEntity {
+        RememberedEntity {
+            position,
+            glyph: to_cp437('g'),
+            name: String::from("Goblin"),
+        }
+    }
+
+    #[test]
+    fn should_keep_entities_that_are_out_of_view() {
+        let mut world = World::default();
+        let goblin = world.push(());
+        let mut memory = EntityMemory::new();
+        memory.update(
+            &HashSet::from([Point::new(1, 1)]),
+            vec![(goblin, goblin_at(Point::new(1, 1)))],
+        );
+
+        memory.update(&HashSet::from([Point::new(5, 5)]), Vec::new());
+
+        assert_eq!(memory.remembered_at(Point::new(1, 1)).count(), 1);
+    }
+
+    #[test]
+    fn should_forget_entities_missing_from_a_visible_tile() {
+        let mut world = World::default();
+        let goblin = world.push(());
+        let mut memory = EntityMemory::new();
+        memory.update(
+            &HashSet::from([Point::new(1, 1)]),
+            vec![(goblin, goblin_at(Point::new(1, 1)))],
+        );
+
+        memory.update(&HashSet::from([Point::new(1, 1)]), Vec::new());
+
+        assert_eq!(memory.remembered().count(), 0);
+    }
+
+    #[test]
+    fn should_move_an_entity_seen_somewhere_else() {
+        let mut world = World::default();
+        let goblin = world.push(());
+        let mut memory = EntityMemory::new();
+        memory.update(
+            &HashSet::from([Point::new(1, 1)]),
+            vec![(goblin, goblin_at(Point::new(1, 1)))],
+        );
+
+        memory.update(
+            &HashSet::from([Point::new(2, 2)]),
+            vec![(goblin, goblin_at(Point::new(2, 2)))],
+        );
+
+        assert_eq!(memory.remembered_at(Point::new(1, 1)).count(), 0);
+        assert_eq!(memory.remembered_at(Point::new(2, 2)).count(), 1);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use crate::prelude::*;
+
+/// A Dijkstra map flowing towards the player, shared by every monster that is
+/// chasing them. It is only rebuilt when the player moves or the map changes,
+/// rather than once per monster turn.
+pub struct FlowField {
+    key: Option<(Point, u32)>,
+    dijkstra_map: DijkstraMap,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlowField {
+    pub fn new() -> Self {
+        Self {
+            key: None,
+            dijkstra_map: DijkstraMap::new_empty(0, 0, 1024.0),
+        }
+    }
+
+    /// Rebuilds the field if it is stale, returning whether any work was done.
+    pub fn update(&mut self, player_pos: Point, map: &Map) -> bool {
+        let key = Some((player_pos, map.version()));
+        if self.key == key {
+            return false;
+        }
+        self.dijkstra_map = DijkstraMap::new(
+            map.width,
+            map.height,
+            &[map.point2d_to_index(player_pos)],
+            map,
+            1024.0,
+        );
+        self.key = key;
+        true
+    }
+
+    pub fn distance(&self, idx: usize) -> f32 {
+        self.dijkstra_map.map[idx]
+    }
+
+    /// The free neighbouring tile that gets closest to the player. If every
+    /// tile closer to the player is taken, a tile no further away is used
+    /// instead so that monsters spread out around each other rather than
+    /// queueing.
+    pub fn step_towards(
+        &self,
+        position: Point,
+        map: &Map,
+        occupied: &HashSet<Point>,
+    ) -> Option<Point> {
+        let idx = map.point2d_to_index(position);
+        let current = self.distance(idx);
+        if current == f32::MAX {
+            return None;
+        }
+        self.free_exits(idx, map, occupied)
+            .into_iter()
+            .filter(|exit| self.distance(*exit) <= current)
+            .min_by(|a, b| self.distance(*a).partial_cmp(&self.distance(*b)).unwrap())
+            .map(|exit| map.index_to_point2d(exit))
+    }
+
+    /// The free neighbouring tile that gets furthest from the player.
+    pub fn step_away(
+        &self,
+        position: Point,
+        map: &Map,
+        occupied: &HashSet<Point>,
+    ) -> Option<Point> {
+        let idx = map.point2d_to_index(position);
+        let current = self.distance(idx);
+        self.free_exits(idx, map, occupied)
+            .into_iter()
+            .filter(|exit| self.distance(*exit) > current && self.distance(*exit) < f32::MAX)
+            .max_by(|a, b| self.distance(*a).partial_cmp(&self.distance(*b)).unwrap())
+            .map(|exit| map.index_to_point2d(exit))
+    }
+
+    fn free_exits(&self, idx: usize, map: &Map, occupied: &HashSet<Point>) -> Vec<usize> {
+        map.get_available_exits(idx)
+            .iter()
+            .map(|(exit, _)| *exit)
+            .filter(|exit| !occupied.contains(&map.index_to_point2d(*exit)))
+            .collect()
+    }
+}
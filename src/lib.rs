@@ -0,0 +1,34 @@
+pub mod camera;
+pub mod components;
+pub mod entity_memory;
+pub mod flow_field;
+pub mod lighting;
+pub mod map;
+pub mod map_builder;
+pub mod mouse;
+pub mod spawner;
+pub mod systems;
+pub mod turn_state;
+pub mod score_tracker;
+
+pub mod prelude {
+    pub use bracket_lib::prelude::*;
+    pub const SCREEN_WIDTH: i32 = 80;
+    pub const SCREEN_HEIGHT: i32 = 50;
+    pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
+    pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
+    pub use crate::camera::*;
+    pub use crate::components::*;
+    pub use crate::entity_memory::*;
+    pub use crate::flow_field::*;
+    pub use crate::lighting::*;
+    pub use crate::map::*;
+    pub use crate::map_builder::*;
+    pub use crate::mouse::*;
+    pub use crate::spawner::budget::Difficulty;
+    pub use crate::spawner::spawn_player;
+    pub use crate::systems::*;
+    pub use crate::turn_state::*;
+    pub use legion::world::*;
+    pub use legion::*;
+}
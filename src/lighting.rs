@@ -0,0 +1,166 @@
+use crate::prelude::*;
+
+/// How brightly, and in what colour, each tile of the map is lit. Every tile
+/// gets the level's ambient light, plus the light of any source that can
+/// see it. It is only rebuilt when a light moves or the map changes, rather
+/// than every frame.
+pub struct LightMap {
+    version: Option<u32>,
+    sources: Vec<(Point, LightSource)>,
+    width: i32,
+    ambient: f32,
+    light: Vec<RGB>,
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightMap {
+    pub fn new() -> Self {
+        Self {
+            version: None,
+            sources: Vec::new(),
+            width: 0,
+            ambient: 1.0,
+            light: Vec::new(),
+        }
+    }
+
+    /// Relights the map if it is stale, returning whether any work was done.
+    pub fn update(&mut self, map: &Map, ambient: f32, sources: &[(Point, LightSource)]) -> bool {
+        if self.version == Some(map.version()) && self.ambient == ambient && self.sources == sources
+        {
+            return false;
+        }
+        self.width = map.width;
+        self.ambient = ambient;
+        self.light = vec![RGB::from_f32(ambient, ambient, ambient); map.tiles.len()];
+        for (position, source) in sources.iter() {
+            let color = RGB::named(source.color);
+            for pt in field_of_view_set(*position, source.range, map) {
+                let distance = DistanceAlg::Pythagoras.distance2d(*position, pt);
+                let falloff = 1.0 - distance / (source.range + 1) as f32;
+                if let Some(idx) = map.try_idx(pt).filter(|_| falloff > 0.0) {
+                    self.light[idx] = self.light[idx] + color * falloff;
+                }
+            }
+        }
+        self.version = Some(map.version());
+        self.sources = sources.to_vec();
+        true
+    }
+
+    /// The light falling on `pt`, each channel capped at full brightness.
+    pub fn light_at(&self, pt: Point) -> RGB {
+        let light = if pt.x >= 0 && pt.x < self.width && pt.y >= 0 {
+            self.light.get((pt.y * self.width + pt.x) as usize)
+        } else {
+            None
+        };
+        match light {
+            Some(light) => RGB::from_f32(light.r, light.g, light.b),
+            None => RGB::from_f32(self.ambient, self.ambient, self.ambient),
+        }
+    }
+
+    /// A tile's colour as it looks under the light falling on it.
+    pub fn apply(&self, pt: Point, tint: RGB) -> RGB {
+        tint * self.light_at(pt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn open_map() -> Map {
+        let mut map = Map::new(20, 10);
+        map.tiles.iter_mut().for_each(|t| *t = TileType::Floor);
+        map
+    }
+
+    #[test]
+    fn should_fade_light_with_distance() {
+        let map = open_map();
+        let mut light_map = LightMap::new();
+        let torch = LightSource {
+            color: (255, 0, 0),
+            range: 5,
+        };
+
+        light_map.update(&map, 0.0, &[(Point::new(5, 5), torch)]);
+
+        let near = light_map.light_at(Point::new(6, 5));
+        let far = light_map.light_at(Point::new(9, 5));
+        assert!(near.r > far.r);
+        assert!(far.r > 0.0);
+        assert_eq!(near.g, 0.0);
+        assert_eq!(light_map.light_at(Point::new(15, 5)).r, 0.0);
+    }
+
+    #[test]
+    fn should_not_light_tiles_behind_walls() {
+        let mut map = open_map();
+        for y in 0..10 {
+            let idx = map.map_idx(7, y);
+            map.tiles[idx] = TileType::Wall;
+        }
+        let mut light_map = LightMap::new();
+        let torch = LightSource {
+            color: (255, 255, 255),
+            range: 6,
+        };
+
+        light_map.update(&map, 0.2, &[(Point::new(5, 5), torch)]);
+
+        assert!(light_map.light_at(Point::new(6, 5)).r > 0.2);
+        assert_eq!(light_map.light_at(Point::new(9, 5)).r, 0.2);
+    }
+
+    #[test]
+    fn should_blend_overlapping_lights_up_to_full_brightness() {
+        let map = open_map();
+        let mut light_map = LightMap::new();
+        let red = LightSource {
+            color: (255, 0, 0),
+            range: 4,
+        };
+        let blue = LightSource {
+            color: (0, 0, 255),
+            range: 4,
+        };
+
+        light_map.update(
+            &map,
+            0.5,
+            &[(Point::new(4, 5), red), (Point::new(6, 5), blue)],
+        );
+
+        let light = light_map.light_at(Point::new(5, 5));
+        assert_eq!(light.r, 1.0);
+        assert_eq!(light.g, 0.5);
+        assert_eq!(light.b, 1.0);
+    }
+
+    #[test]
+    fn should_only_relight_when_a_light_moves_or_the_map_changes() {
+        let mut map = open_map();
+        let mut light_map = LightMap::new();
+        let torch = LightSource {
+            color: (255, 255, 255),
+            range: 4,
+        };
+
+        assert!(light_map.update(&map, 0.5, &[(Point::new(5, 5), torch)]));
+        assert!(!light_map.update(&map, 0.5, &[(Point::new(5, 5), torch)]));
+        assert!(light_map.update(&map, 0.5, &[(Point::new(6, 5), torch)]));
+
+        let idx = map.map_idx(7, 5);
+        map.set_tile(idx, TileType::Wall);
+
+        assert!(light_map.update(&map, 0.5, &[(Point::new(6, 5), torch)]));
+    }
+}
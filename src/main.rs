@@ -1,35 +1,11 @@
-mod camera;
-mod components;
-mod map;
-mod map_builder;
-mod spawner;
-mod systems;
-mod turn_state;
-mod score_tracker;
-
-mod prelude {
-    pub use bracket_lib::prelude::*;
-    pub const SCREEN_WIDTH: i32 = 80;
-    pub const SCREEN_HEIGHT: i32 = 50;
-    pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
-    pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
-    pub use crate::camera::*;
-    pub use crate::components::*;
-    pub use crate::map::*;
-    pub use crate::map_builder::*;
-    pub use crate::spawner::spawn_player;
-    pub use crate::systems::*;
-    pub use crate::turn_state::*;
-    pub use legion::world::*;
-    pub use legion::*;
-}
-
 use std::collections::HashSet;
+use std::path::PathBuf;
 
 use legion::systems::CommandBuffer;
-use prelude::*;
-use score_tracker::ScoreTracker;
-use spawner::{spawn_amulet_of_yala, spawn_level};
+use rusty_roguelike::prelude::*;
+use rusty_roguelike::score_tracker::ScoreTracker;
+use rusty_roguelike::spawner::template::{Spawner, TemplateSpawner};
+use rusty_roguelike::spawner::{load_templates, spawn_amulet_of_yala, spawn_key, spawn_level};
 
 struct State {
     ecs: World,
@@ -37,30 +13,135 @@ struct State {
     input_systems: Schedule,
     player_systems: Schedule,
     monster_systems: Schedule,
+    mapgen: MapGenDebug,
+}
+
+/// Debug options for watching, or saving, the steps that built each level.
+/// `--show-mapgen` replays them on screen before the level starts and
+/// `--dump-mapgen=<dir>` writes them out as text files.
+struct MapGenDebug {
+    show: bool,
+    dump_dir: Option<PathBuf>,
+    history: Vec<Map>,
+    step: usize,
+    timer: f32,
+}
+
+impl MapGenDebug {
+    /// Milliseconds each snapshot stays on screen.
+    const STEP_TIME: f32 = 100.0;
+
+    fn from_args() -> Self {
+        let mut mapgen = Self {
+            show: false,
+            dump_dir: None,
+            history: Vec::new(),
+            step: 0,
+            timer: 0.0,
+        };
+        for arg in std::env::args().skip(1) {
+            if arg == "--show-mapgen" {
+                mapgen.show = true;
+            } else if let Some(dir) = arg.strip_prefix("--dump-mapgen=") {
+                mapgen.dump_dir = Some(PathBuf::from(dir));
+            }
+        }
+        mapgen
+    }
+
+    /// Takes the snapshots from a freshly built level, dumping them if asked
+    /// to, and returns the turn state the level should start in.
+    fn record(&mut self, map_builder: &mut MapBuilder, level: usize) -> TurnState {
+        if let Some(dir) = &self.dump_dir {
+            if let Err(e) = map_builder.dump_history(&dir.join(format!("level_{}", level))) {
+                eprintln!("Unable to dump map generation snapshots: {}", e);
+            }
+        }
+        self.history = std::mem::take(&mut map_builder.history);
+        self.step = 0;
+        self.timer = 0.0;
+        if self.show && !self.history.is_empty() {
+            TurnState::ShowingMapGen
+        } else {
+            TurnState::AwaitingInput
+        }
+    }
+
+    /// Draws the current snapshot and moves on to the next once its time is
+    /// up. Returns true when every snapshot has been shown, or a key was
+    /// pressed to skip them.
+    fn render(&mut self, ctx: &mut BTerm, theme: &dyn MapTheme) -> bool {
+        let map = &self.history[self.step];
+        ctx.set_active_console(2);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let tile = map.tiles[map.map_idx(x, y)];
+                ctx.set(
+                    x,
+                    y,
+                    theme.tile_tint(tile),
+                    BLACK,
+                    theme.tile_to_render(tile),
+                );
+            }
+        }
+        ctx.print_color(
+            0,
+            map.height + 1,
+            WHITE,
+            BLACK,
+            format!(
+                "Generating level: step {} of {}. Press any key to skip.",
+                self.step + 1,
+                self.history.len()
+            ),
+        );
+
+        self.timer += ctx.frame_time_ms;
+        if self.timer > Self::STEP_TIME {
+            self.timer = 0.0;
+            self.step += 1;
+        }
+        let finished = ctx.key.is_some() || self.step >= self.history.len();
+        if finished {
+            self.history.clear();
+        }
+        finished
+    }
 }
 
 impl State {
     fn new() -> Self {
-        let mut ecs = World::default();
         let mut resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-        let mut score_tracker = ScoreTracker::new();
-        spawn_player(&mut ecs, map_builder.player_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(&mut ecs, &mut rng, 0, &map_builder.spawn_locations);
-        resources.insert(map_builder.map);
-        resources.insert(Camera::new(map_builder.player_start));
-        resources.insert(TurnState::AwaitingInput);
-        resources.insert(map_builder.theme);
-        resources.insert(score_tracker);
+        resources.insert(TurnState::StartScreen);
         Self {
-            ecs,
+            ecs: World::default(),
             resources,
             input_systems: build_input_scheduler(),
             player_systems: build_player_scheduler(),
             monster_systems: build_monster_scheduler(),
+            mapgen: MapGenDebug::from_args(),
+        }
+    }
+
+    fn start_screen(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(2, YELLOW, BLACK, "Rusty Roguelike");
+        ctx.print_color_centered(
+            4,
+            WHITE,
+            BLACK,
+            "Descend into the dungeon and recover the Amulet of Yala to save your home town.",
+        );
+        ctx.print_color_centered(
+            7,
+            GREEN,
+            BLACK,
+            "Press 1 to play on Easy, 2 on Normal or 3 on Hard.",
+        );
+
+        if let Some(difficulty) = Self::chosen_difficulty(ctx.key) {
+            self.reset_game_state(difficulty);
         }
     }
 
@@ -85,10 +166,15 @@ impl State {
             BLACK,
             "Don't worry, you can always try again with a new hero.",
         );
-        ctx.print_color_centered(9, GREEN, BLACK, "Press 1 to play again.");
+        ctx.print_color_centered(
+            9,
+            GREEN,
+            BLACK,
+            "Press 1 to play again on Easy, 2 on Normal or 3 on Hard.",
+        );
 
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+        if let Some(difficulty) = Self::chosen_difficulty(ctx.key) {
+            self.reset_game_state(difficulty);
         }
     }
 
@@ -99,7 +185,7 @@ impl State {
             4,
             WHITE,
             BLACK,
-            "You put on the Amulet of Yala and feel its power course through your veins.",
+            "With its guardian slain, you put on the Amulet of Yala and feel its power course through your veins.",
         );
         ctx.print_color_centered(
             5,
@@ -107,7 +193,12 @@ impl State {
             BLACK,
             "Your town is saved, and you can go back to your normal life.",
         );
-        ctx.print_color_centered(7, GREEN, BLACK, "Press 1 to play again.");
+        ctx.print_color_centered(
+            7,
+            GREEN,
+            BLACK,
+            "Press 1 to play again on Easy, 2 on Normal or 3 on Hard.",
+        );
 
         {
             let score_tracker = self.resources.get::<ScoreTracker>().expect("Failure to retrieve score tracker from resources");
@@ -117,26 +208,56 @@ impl State {
             ctx.print_color_centered(10, YELLOW, BLACK, format!("Your time: {}:{}", minutes, seconds));
         }
 
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+        if let Some(difficulty) = Self::chosen_difficulty(ctx.key) {
+            self.reset_game_state(difficulty);
         }
     }
 
-    fn reset_game_state(&mut self) {
+    fn chosen_difficulty(key: Option<VirtualKeyCode>) -> Option<Difficulty> {
+        match key {
+            Some(VirtualKeyCode::Key1) => Some(Difficulty::Easy),
+            Some(VirtualKeyCode::Key2) => Some(Difficulty::Normal),
+            Some(VirtualKeyCode::Key3) => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    fn reset_game_state(&mut self, difficulty: Difficulty) {
         self.ecs = World::default();
         self.resources = Resources::default();
         let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        let mut map_builder = MapBuilder::new(&mut rng, 0);
         spawn_player(&mut self.ecs, map_builder.player_start);
+        set_player_sight(&mut self.ecs, map_builder.theme.visibility_radius());
+        if let Some(key) = map_builder.key_start {
+            spawn_key(&mut self.ecs, key);
+        }
         let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
         map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(&mut self.ecs, &mut rng, 0, &map_builder.spawn_locations);
+        let templates = load_templates();
+        spawn_level(
+            &mut self.ecs,
+            &templates,
+            &mut rng,
+            0,
+            map_builder.theme.name(),
+            difficulty,
+            &map_builder.spawn_locations,
+            &map_builder.map,
+        );
         let score_tracker = ScoreTracker::new();
+        let turn_state = self.mapgen.record(&mut map_builder, 0);
         self.resources.insert(map_builder.map);
         self.resources.insert(Camera::new(map_builder.player_start));
-        self.resources.insert(TurnState::AwaitingInput);
+        self.resources.insert(turn_state);
         self.resources.insert(map_builder.theme);
         self.resources.insert(score_tracker);
+        self.resources.insert(EntityMemory::new());
+        self.resources.insert(rng);
+        self.resources.insert(FlowField::new());
+        self.resources.insert(LightMap::new());
+        self.resources.insert(templates);
+        self.resources.insert(difficulty);
     }
 
     fn advance_level(&mut self) {
@@ -168,36 +289,84 @@ impl State {
             .iter_mut(&mut self.ecs)
             .for_each(|fov| fov.is_dirty = true);
 
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        let mut rng = self
+            .resources
+            .remove::<RandomNumberGenerator>()
+            .unwrap_or_else(RandomNumberGenerator::new);
+        let map_level = <&Player>::query()
+            .iter(&self.ecs)
+            .map(|player| player.map_level + 1)
+            .next()
+            .unwrap_or(0);
+        let mut map_builder = MapBuilder::new(&mut rng, map_level as usize);
 
-        let mut map_level = 0;
         <(&mut Player, &mut Point)>::query()
             .iter_mut(&mut self.ecs)
             .for_each(|(player, pos)| {
-                player.map_level += 1;
-                map_level = player.map_level;
+                player.map_level = map_level;
                 pos.x = map_builder.player_start.x;
                 pos.y = map_builder.player_start.y;
             });
 
+        set_player_sight(&mut self.ecs, map_builder.theme.visibility_radius());
+
+        if let Some(key) = map_builder.key_start {
+            spawn_key(&mut self.ecs, key);
+        }
+
         if map_level == 2 {
+            map_builder.build_vault();
             spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
         } else {
             let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
             map_builder.map.tiles[exit_idx] = TileType::Exit;
         }
 
-        spawn_level(
-            &mut self.ecs,
-            &mut rng,
-            map_level as usize,
-            &map_builder.spawn_locations,
-        );
+        {
+            let templates = self
+                .resources
+                .get::<TemplateSpawner<Spawner>>()
+                .expect("Failure to retrieve templates from resources");
+            let difficulty = *self
+                .resources
+                .get::<Difficulty>()
+                .expect("Failure to retrieve difficulty from resources");
+            spawn_level(
+                &mut self.ecs,
+                &templates,
+                &mut rng,
+                map_level as usize,
+                map_builder.theme.name(),
+                difficulty,
+                &map_builder.spawn_locations,
+                &map_builder.map,
+            );
+            if map_level == 2 {
+                templates.spawn_boss(&mut self.ecs, map_level as usize, map_builder.amulet_start);
+            }
+        }
+        let turn_state = self.mapgen.record(&mut map_builder, map_level as usize);
         self.resources.insert(map_builder.map);
         self.resources.insert(Camera::new(map_builder.player_start));
-        self.resources.insert(TurnState::AwaitingInput);
+        self.resources.insert(turn_state);
         self.resources.insert(map_builder.theme);
+        self.resources.insert(EntityMemory::new());
+        self.resources.insert(rng);
+        self.resources.insert(FlowField::new());
+        self.resources.insert(LightMap::new());
+    }
+
+    fn show_mapgen(&mut self, ctx: &mut BTerm) {
+        let finished = {
+            let theme = self
+                .resources
+                .get::<Box<dyn MapTheme>>()
+                .expect("Failure to retrieve theme from resources");
+            self.mapgen.render(ctx, theme.as_ref())
+        };
+        if finished {
+            self.resources.insert(TurnState::AwaitingInput);
+        }
     }
 
     fn clear_screen_effects(&mut self) {
@@ -214,12 +383,13 @@ impl GameState for State {
         self.resources.insert(ctx.key);
         ctx.set_active_console(0);
         self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
+        self.resources.insert(MouseClick(ctx.left_click));
         ctx.cls();
         ctx.set_active_console(1);
         ctx.cls();
         ctx.set_active_console(2);
         ctx.cls();
-        let current_state = self.resources.get::<TurnState>().unwrap().clone();
+        let current_state = *self.resources.get::<TurnState>().unwrap();
         match current_state {
             TurnState::AwaitingInput => {
                 self.input_systems.execute(&mut self.ecs, &mut self.resources);
@@ -236,11 +406,24 @@ impl GameState for State {
             TurnState::NextLevel => {
                 self.advance_level();
             }
+            TurnState::StartScreen => self.start_screen(ctx),
+            TurnState::ShowingMapGen => self.show_mapgen(ctx),
         }
         render_draw_buffer(ctx).expect("Render error");
     }
 }
 
+/// Lets the player see as far as the level's theme allows.
+fn set_player_sight(ecs: &mut World, radius: i32) {
+    <&mut FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter_mut(ecs)
+        .for_each(|fov| {
+            fov.radius = radius;
+            fov.is_dirty = true;
+        });
+}
+
 fn main() -> BError {
     let context = BTermBuilder::new()
         .with_title("Rusty Roguelike")
@@ -1,65 +1,146 @@
+use serde::Deserialize;
+
 use crate::prelude::*;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
 pub enum TileType {
     Floor,
     Wall,
     Exit,
+    Door,
+    OpenDoor,
+    LockedDoor,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Rubble,
 }
 
+impl TileType {
+    /// What it costs to step onto this tile when pathing, or `None` if it
+    /// can't be walked onto at all.
+    pub fn movement_cost(&self) -> Option<f32> {
+        match self {
+            TileType::Floor | TileType::Exit | TileType::Door | TileType::OpenDoor => Some(1.0),
+            TileType::Rubble => Some(1.5),
+            TileType::ShallowWater => Some(2.0),
+            TileType::Lava => Some(10.0),
+            TileType::Wall | TileType::LockedDoor | TileType::DeepWater => None,
+        }
+    }
+
+    /// A plain-text character for the tile, used when writing maps out as
+    /// text rather than drawing them.
+    pub fn to_ascii(&self) -> char {
+        match self {
+            TileType::Floor => '.',
+            TileType::Wall => '#',
+            TileType::Exit => '>',
+            TileType::Door => '+',
+            TileType::OpenDoor => '\'',
+            TileType::LockedDoor => '&',
+            TileType::ShallowWater => '~',
+            TileType::DeepWater => '=',
+            TileType::Lava => '%',
+            TileType::Rubble => ':',
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Map {
+    pub width: i32,
+    pub height: i32,
     pub tiles: Vec<TileType>,
     pub revealed_tiles: Vec<bool>,
+    version: u32,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
 }
 
 impl Map {
-    pub fn new() -> Self {
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = (width * height) as usize;
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES],
-            revealed_tiles: vec![false; NUM_TILES],
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed_tiles: vec![false; num_tiles],
+            version: 0,
         }
     }
 
+    /// Changes a tile during play. Anything caching pathing information about
+    /// the map can compare `version` to see that it needs rebuilding.
+    pub fn set_tile(&mut self, idx: usize, tile: TileType) {
+        if self.tiles[idx] != tile {
+            self.tiles[idx] = tile;
+            self.version += 1;
+        }
+    }
+
+    /// The whole map as lines of text, one character per tile.
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::with_capacity(self.tiles.len() + self.height as usize);
+        self.tiles.chunks(self.width as usize).for_each(|row| {
+            text.extend(row.iter().map(|t| t.to_ascii()));
+            text.push('\n');
+        });
+        text
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
     pub fn in_bounds(&self, point: Point) -> bool {
-        point.x >= 0 && point.x < SCREEN_WIDTH && point.y >= 0 && point.y < SCREEN_HEIGHT
+        point.x >= 0 && point.x < self.width && point.y >= 0 && point.y < self.height
     }
 
     pub fn can_enter_tile(&self, point: Point) -> bool {
-        self.in_bounds(point) && self.tiles[map_idx(point.x, point.y)] == TileType::Floor
-            || self.in_bounds(point) && self.tiles[map_idx(point.x, point.y)] == TileType::Exit
+        self.in_bounds(point)
+            && !self.is_closed_door(point)
+            && self.tiles[self.map_idx(point.x, point.y)]
+                .movement_cost()
+                .is_some()
+    }
+
+    /// A closed door can't be stood in, but anything walking into it opens
+    /// it, so pathing treats it as a way through.
+    pub fn is_closed_door(&self, point: Point) -> bool {
+        self.in_bounds(point) && self.tiles[self.map_idx(point.x, point.y)] == TileType::Door
+    }
+
+    pub fn map_idx(&self, x: i32, y: i32) -> usize {
+        ((y * self.width) + x) as usize
     }
 
     pub fn try_idx(&self, point: Point) -> Option<usize> {
         if !self.in_bounds(point) {
             None
         } else {
-            Some(map_idx(point.x, point.y))
+            Some(self.map_idx(point.x, point.y))
         }
     }
 
-    fn valid_exit(&self, location: Point, delta: Point) -> Option<usize> {
+    fn valid_exit(&self, location: Point, delta: Point) -> Option<(usize, f32)> {
         let destination = location + delta;
         if self.in_bounds(destination) {
-            if self.can_enter_tile(destination) {
-                let idx = self.point2d_to_index(destination);
-                Some(idx)
-            } else {
-                None
-            }
+            let idx = self.point2d_to_index(destination);
+            self.tiles[idx].movement_cost().map(|cost| (idx, cost))
         } else {
             None
         }
     }
 }
 
-pub fn map_idx(x: i32, y: i32) -> usize {
-    ((y * SCREEN_WIDTH) + x) as usize
-}
-
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
-        Point::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+        Point::new(self.width, self.height)
     }
 
     fn in_bounds(&self, point: Point) -> bool {
@@ -69,24 +150,31 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx] != TileType::Floor
+        !matches!(
+            self.tiles[idx],
+            TileType::Floor
+                | TileType::OpenDoor
+                | TileType::ShallowWater
+                | TileType::DeepWater
+                | TileType::Lava
+        )
     }
 
     fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
         let mut exits = SmallVec::new();
         let location = self.index_to_point2d(idx);
 
-        if let Some(idx) = self.valid_exit(location, Point::new(-1, 0)) {
-            exits.push((idx, 1.0))
+        if let Some(exit) = self.valid_exit(location, Point::new(-1, 0)) {
+            exits.push(exit)
         }
-        if let Some(idx) = self.valid_exit(location, Point::new(1, 0)) {
-            exits.push((idx, 1.0))
+        if let Some(exit) = self.valid_exit(location, Point::new(1, 0)) {
+            exits.push(exit)
         }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, -1)) {
-            exits.push((idx, 1.0))
+        if let Some(exit) = self.valid_exit(location, Point::new(0, -1)) {
+            exits.push(exit)
         }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, 1)) {
-            exits.push((idx, 1.0))
+        if let Some(exit) = self.valid_exit(location, Point::new(0, 1)) {
+            exits.push(exit)
         }
         exits
     }
@@ -1,21 +1,28 @@
 use super::{themes::DungeonTheme, MapArchitect};
 use crate::prelude::*;
 
+const WIDTH: i32 = 120;
+const HEIGHT: i32 = 80;
+
 pub struct CellularAutomataArchitect {}
 
 impl MapArchitect for CellularAutomataArchitect {
     fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(WIDTH, HEIGHT),
             rooms: Vec::new(),
             spawn_locations: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
             theme: DungeonTheme::new(),
         };
         self.random_noise_map(rng, &mut mb.map);
+        mb.take_snapshot();
         for _ in 0..10 {
             self.iteration(&mut mb.map);
+            mb.take_snapshot();
         }
         let start = self.find_start(&mb.map);
         mb.spawn_locations = mb.get_spawn_plan(&start, rng);
@@ -41,7 +48,7 @@ impl CellularAutomataArchitect {
         let mut neighbours = 0;
         for iy in -1..=1 {
             for ix in -1..=1 {
-                if !(ix == 0 && iy == 0) && map.tiles[map_idx(x + ix, y + iy)] == TileType::Wall {
+                if !(ix == 0 && iy == 0) && map.tiles[map.map_idx(x + ix, y + iy)] == TileType::Wall {
                     neighbours += 1;
                 }
             }
@@ -49,12 +56,12 @@ impl CellularAutomataArchitect {
         neighbours
     }
 
-    fn iteration(&mut self, map: &mut Map) {
+    pub(super) fn iteration(&mut self, map: &mut Map) {
         let mut new_tiles = map.tiles.clone();
-        for y in 1..SCREEN_HEIGHT - 1 {
-            for x in 1..SCREEN_WIDTH - 1 {
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
                 let neighbours = self.count_neighbours(x, y, map);
-                let idx = map_idx(x, y);
+                let idx = map.map_idx(x, y);
                 if neighbours > 4 || neighbours == 0 {
                     new_tiles[idx] = TileType::Wall;
                 } else {
@@ -66,7 +73,7 @@ impl CellularAutomataArchitect {
     }
 
     fn find_start(&self, map: &Map) -> Point {
-        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        let center = Point::new(map.width / 2, map.height / 2);
         let closest_point = map
             .tiles
             .iter()
@@ -78,7 +85,7 @@ impl CellularAutomataArchitect {
                     DistanceAlg::Pythagoras.distance2d(center, map.index_to_point2d(idx)),
                 )
             })
-            .min_by(|(_, distance), (_, distance2)| distance.partial_cmp(&distance2).unwrap())
+            .min_by(|(_, distance), (_, distance2)| distance.partial_cmp(distance2).unwrap())
             .map(|(idx, _)| idx)
             .unwrap();
         map.index_to_point2d(closest_point)
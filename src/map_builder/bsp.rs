@@ -0,0 +1,255 @@
+use super::{themes::DungeonTheme, MapArchitect};
+use crate::{prelude::*, spawner::template::EntityType};
+
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 50;
+/// Areas narrower than twice this aren't split any further along that axis.
+const MIN_LEAF: i32 = 10;
+const MAX_DEPTH: i32 = 4;
+const MIN_ROOM: i32 = 3;
+const NUM_TREASURE_ROOMS: usize = 2;
+const NUM_MONSTER_DENS: usize = 2;
+const TREASURE_PER_ROOM: usize = 2;
+const MONSTERS_PER_DEN: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RoomKind {
+    Entrance,
+    Treasure,
+    MonsterDen,
+    Plain,
+}
+
+/// Splits the map in two again and again, puts a room in each of the
+/// resulting leaves and joins every pair of siblings with a corridor.
+pub struct BspArchitect {}
+
+impl MapArchitect for BspArchitect {
+    fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(WIDTH, HEIGHT),
+            rooms: Vec::new(),
+            spawn_locations: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
+            theme: DungeonTheme::new(),
+        };
+
+        mb.fill(TileType::Wall);
+        self.partition(
+            &mut mb,
+            Rect::with_size(1, 1, WIDTH - 2, HEIGHT - 2),
+            MAX_DEPTH,
+            rng,
+        );
+        mb.build_doors();
+        mb.take_snapshot();
+        mb.player_start = mb.rooms[0].center();
+        mb.amulet_start = mb.find_most_distant();
+        if rng.range(0, 2) == 0 {
+            mb.lock_a_door(rng);
+        }
+
+        let kinds = self.assign_kinds(mb.rooms.len(), rng);
+        for (room, kind) in mb.rooms.clone().iter().zip(kinds) {
+            let (preferred_entity, count) = match kind {
+                RoomKind::Entrance => continue,
+                RoomKind::Treasure => (Some(EntityType::Item), TREASURE_PER_ROOM),
+                RoomKind::MonsterDen => (Some(EntityType::Enemy), MONSTERS_PER_DEN),
+                RoomKind::Plain => {
+                    mb.spawn_locations.push(SpawnLocation {
+                        point: room.center(),
+                        preferred_entity: None,
+                        template: None,
+                    });
+                    continue;
+                }
+            };
+            let mut points: Vec<Point> = room
+                .point_set()
+                .into_iter()
+                .filter(|pt| *pt != mb.amulet_start && mb.key_start != Some(*pt))
+                .collect();
+            points.sort_by_key(|pt| (pt.y, pt.x));
+            for _ in 0..count {
+                let point = match rng.random_slice_index(&points) {
+                    Some(idx) => points.remove(idx),
+                    None => break,
+                };
+                mb.spawn_locations.push(SpawnLocation {
+                    point,
+                    preferred_entity,
+                    template: None,
+                });
+            }
+        }
+
+        mb
+    }
+}
+
+impl BspArchitect {
+    /// Carves the rooms for `area` and returns them, joining the two halves
+    /// with a corridor wherever the area was split.
+    fn partition(
+        &mut self,
+        mb: &mut MapBuilder,
+        area: Rect,
+        depth: i32,
+        rng: &mut RandomNumberGenerator,
+    ) -> Vec<Rect> {
+        let can_split_x = area.width() >= MIN_LEAF * 2;
+        let can_split_y = area.height() >= MIN_LEAF * 2;
+        if depth == 0 || !(can_split_x || can_split_y) {
+            let room = self.place_room(area, rng);
+            room.for_each(|pt| {
+                let idx = mb.map.map_idx(pt.x, pt.y);
+                mb.map.tiles[idx] = TileType::Floor;
+            });
+            mb.rooms.push(room);
+            mb.take_snapshot();
+            return vec![room];
+        }
+
+        let split_x = if can_split_x && can_split_y {
+            area.width() >= area.height()
+        } else {
+            can_split_x
+        };
+        let (first, second) = if split_x {
+            let cut = rng.range(MIN_LEAF, area.width() - MIN_LEAF + 1);
+            (
+                Rect::with_size(area.x1, area.y1, cut, area.height()),
+                Rect::with_size(area.x1 + cut, area.y1, area.width() - cut, area.height()),
+            )
+        } else {
+            let cut = rng.range(MIN_LEAF, area.height() - MIN_LEAF + 1);
+            (
+                Rect::with_size(area.x1, area.y1, area.width(), cut),
+                Rect::with_size(area.x1, area.y1 + cut, area.width(), area.height() - cut),
+            )
+        };
+
+        let mut rooms = self.partition(mb, first, depth - 1, rng);
+        let others = self.partition(mb, second, depth - 1, rng);
+        self.connect(mb, &rooms, &others, rng);
+        mb.take_snapshot();
+        rooms.extend(others);
+        rooms
+    }
+
+    /// A room somewhere inside `leaf`, leaving at least one tile of wall
+    /// between it and the leaf's edge.
+    fn place_room(&self, leaf: Rect, rng: &mut RandomNumberGenerator) -> Rect {
+        let width = rng.range(MIN_ROOM, leaf.width() - 1);
+        let height = rng.range(MIN_ROOM, leaf.height() - 1);
+        Rect::with_size(
+            rng.range(leaf.x1 + 1, leaf.x2 - width),
+            rng.range(leaf.y1 + 1, leaf.y2 - height),
+            width,
+            height,
+        )
+    }
+
+    /// Joins the closest pair of rooms on either side of a split.
+    fn connect(
+        &self,
+        mb: &mut MapBuilder,
+        rooms: &[Rect],
+        others: &[Rect],
+        rng: &mut RandomNumberGenerator,
+    ) {
+        let closest = rooms
+            .iter()
+            .flat_map(|a| others.iter().map(move |b| (a.center(), b.center())))
+            .min_by(|(a1, b1), (a2, b2)| {
+                let d1 = DistanceAlg::Pythagoras.distance2d(*a1, *b1);
+                let d2 = DistanceAlg::Pythagoras.distance2d(*a2, *b2);
+                d1.partial_cmp(&d2).unwrap()
+            });
+        if let Some((from, to)) = closest {
+            if rng.range(0, 2) == 1 {
+                mb.apply_horizontal_tunnel(from.x, to.x, from.y);
+                mb.apply_vertical_tunnel(from.y, to.y, to.x);
+            } else {
+                mb.apply_vertical_tunnel(from.y, to.y, from.x);
+                mb.apply_horizontal_tunnel(from.x, to.x, to.y);
+            }
+        }
+    }
+
+    /// The first room is the entrance; a few of the rest are set aside as
+    /// treasure rooms and monster dens.
+    fn assign_kinds(&self, num_rooms: usize, rng: &mut RandomNumberGenerator) -> Vec<RoomKind> {
+        let mut kinds = vec![RoomKind::Plain; num_rooms];
+        if let Some(entrance) = kinds.first_mut() {
+            *entrance = RoomKind::Entrance;
+        }
+        let mut candidates: Vec<usize> = (1..num_rooms).collect();
+        let special = std::iter::repeat_n(RoomKind::Treasure, NUM_TREASURE_ROOMS)
+            .chain(std::iter::repeat_n(RoomKind::MonsterDen, NUM_MONSTER_DENS));
+        for kind in special {
+            match rng.random_slice_index(&candidates) {
+                Some(idx) => kinds[candidates.remove(idx)] = kind,
+                None => break,
+            }
+        }
+        kinds
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_connect_every_room() {
+        let mut rng = RandomNumberGenerator::seeded(5);
+        let mut mb = BspArchitect {}.create_map_builder(&mut rng);
+        mb.map.tiles.iter_mut().for_each(|t| {
+            if *t == TileType::LockedDoor {
+                *t = TileType::Door;
+            }
+        });
+
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+
+        assert!(mb.rooms.len() > 4);
+        mb.rooms.iter().for_each(|room| {
+            assert!(dijkstra_map.map[mb.map.point2d_to_index(room.center())] < f32::MAX);
+        });
+    }
+
+    #[test]
+    fn should_fill_treasure_rooms_and_dens_but_not_the_entrance() {
+        let mut rng = RandomNumberGenerator::seeded(5);
+        let mb = BspArchitect {}.create_map_builder(&mut rng);
+
+        let count = |entity_type| {
+            mb.spawn_locations
+                .iter()
+                .filter(|spawn| spawn.preferred_entity == Some(entity_type))
+                .count()
+        };
+        assert_eq!(
+            count(EntityType::Item),
+            NUM_TREASURE_ROOMS * TREASURE_PER_ROOM
+        );
+        assert_eq!(
+            count(EntityType::Enemy),
+            NUM_MONSTER_DENS * MONSTERS_PER_DEN
+        );
+        assert!(mb
+            .spawn_locations
+            .iter()
+            .all(|spawn| !mb.rooms[0].point_in_rect(spawn.point)));
+    }
+}
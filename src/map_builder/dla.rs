@@ -0,0 +1,131 @@
+use super::{themes::DungeonTheme, MapArchitect};
+use crate::prelude::*;
+
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 50;
+const DESIRED_FLOOR: usize = (WIDTH * HEIGHT) as usize / 4;
+/// How far a particle may wander before it is given up on.
+const MAX_STEPS: usize = 2000;
+const PARTICLES_PER_SNAPSHOT: usize = 100;
+
+/// Grows a branching cave by diffusion-limited aggregation: particles are
+/// released at random, drift towards the middle of the map, and stick where
+/// they first touch the cave.
+pub struct DlaArchitect {}
+
+impl MapArchitect for DlaArchitect {
+    fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(WIDTH, HEIGHT),
+            rooms: Vec::new(),
+            spawn_locations: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
+            theme: DungeonTheme::new(),
+        };
+
+        mb.fill(TileType::Wall);
+        let center = Point::new(WIDTH / 2, HEIGHT / 2);
+        for y in center.y - 1..=center.y + 1 {
+            for x in center.x - 1..=center.x + 1 {
+                let idx = mb.map.map_idx(x, y);
+                mb.map.tiles[idx] = TileType::Floor;
+            }
+        }
+        mb.take_snapshot();
+
+        let mut released = 0;
+        while mb
+            .map
+            .tiles
+            .iter()
+            .filter(|t| **t == TileType::Floor)
+            .count()
+            < DESIRED_FLOOR
+        {
+            self.release_particle(&center, rng, &mut mb.map);
+            released += 1;
+            if released % PARTICLES_PER_SNAPSHOT == 0 {
+                mb.take_snapshot();
+            }
+        }
+        mb.take_snapshot();
+
+        mb.spawn_locations = mb.get_spawn_plan(&center, rng);
+        mb.player_start = center;
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+}
+
+impl DlaArchitect {
+    fn release_particle(&mut self, center: &Point, rng: &mut RandomNumberGenerator, map: &mut Map) {
+        let mut pos = Point::new(rng.range(1, map.width - 1), rng.range(1, map.height - 1));
+        if map.tiles[map.point2d_to_index(pos)] == TileType::Floor {
+            return;
+        }
+
+        for _ in 0..MAX_STEPS {
+            let step = if rng.range(0, 2) == 0 {
+                Point::new((center.x - pos.x).signum(), (center.y - pos.y).signum())
+            } else {
+                match rng.range(0, 4) {
+                    0 => Point::new(-1, 0),
+                    1 => Point::new(1, 0),
+                    2 => Point::new(0, -1),
+                    _ => Point::new(0, 1),
+                }
+            };
+            let step = if step.x != 0 && step.y != 0 {
+                if rng.range(0, 2) == 0 {
+                    Point::new(step.x, 0)
+                } else {
+                    Point::new(0, step.y)
+                }
+            } else {
+                step
+            };
+            let next = pos + step;
+            if next.x < 1 || next.x > map.width - 2 || next.y < 1 || next.y > map.height - 2 {
+                continue;
+            }
+            if map.tiles[map.point2d_to_index(next)] == TileType::Floor {
+                let idx = map.point2d_to_index(pos);
+                map.tiles[idx] = TileType::Floor;
+                return;
+            }
+            pos = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_grow_a_single_connected_cave() {
+        let mut rng = RandomNumberGenerator::seeded(6);
+        let mb = DlaArchitect {}.create_map_builder(&mut rng);
+
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+        let floor = mb
+            .map
+            .tiles
+            .iter()
+            .filter(|t| **t == TileType::Floor)
+            .count();
+        let reached = dijkstra_map.map.iter().filter(|d| **d < f32::MAX).count();
+
+        assert!(floor >= DESIRED_FLOOR);
+        assert_eq!(reached, floor);
+    }
+}
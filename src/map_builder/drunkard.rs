@@ -2,25 +2,29 @@ use super::{themes::DungeonTheme, MapArchitect};
 use crate::prelude::*;
 
 const STAGGER_DISTANCE: usize = 400;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
-const DESIRED_FLOOR: usize = NUM_TILES / 3;
+const WIDTH: i32 = 100;
+const HEIGHT: i32 = 70;
+const DESIRED_FLOOR: usize = (WIDTH * HEIGHT) as usize / 3;
 
 pub struct DrunkardsWalkArchitect {}
 
 impl MapArchitect for DrunkardsWalkArchitect {
     fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(WIDTH, HEIGHT),
             rooms: Vec::new(),
             spawn_locations: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
             theme: DungeonTheme::new(),
         };
 
         mb.fill(TileType::Wall);
-        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        let center = Point::new(WIDTH / 2, HEIGHT / 2);
         self.drunkard(&center, rng, &mut mb.map);
+        mb.take_snapshot();
 
         while mb
             .map
@@ -31,14 +35,14 @@ impl MapArchitect for DrunkardsWalkArchitect {
             < DESIRED_FLOOR
         {
             self.drunkard(
-                &Point::new(rng.range(0, SCREEN_WIDTH), rng.range(0, SCREEN_HEIGHT)),
+                &Point::new(rng.range(0, WIDTH), rng.range(0, HEIGHT)),
                 rng,
                 &mut mb.map,
             );
             let dijkstra_map = DijkstraMap::new(
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-                &vec![mb.map.point2d_to_index(center)],
+                WIDTH,
+                HEIGHT,
+                &[mb.map.point2d_to_index(center)],
                 &mb.map,
                 1024.0,
             );
@@ -48,6 +52,7 @@ impl MapArchitect for DrunkardsWalkArchitect {
                 .enumerate()
                 .filter(|(_, distance)| *distance > &2000.0)
                 .for_each(|(idx, _)| mb.map.tiles[idx] = TileType::Wall);
+            mb.take_snapshot();
         }
 
         mb.spawn_locations = mb.get_spawn_plan(&center, rng);
@@ -59,7 +64,7 @@ impl MapArchitect for DrunkardsWalkArchitect {
 
 impl DrunkardsWalkArchitect {
     fn drunkard(&mut self, start: &Point, rng: &mut RandomNumberGenerator, map: &mut Map) {
-        let mut drunkard_pos = start.clone();
+        let mut drunkard_pos = *start;
         let mut distance_staggered = 0;
 
         loop {
@@ -1,26 +1,31 @@
 use super::{themes::DungeonTheme, MapArchitect};
 use crate::prelude::*;
 
+#[allow(dead_code)]
 pub struct EmptyArchitect {}
 
 impl MapArchitect for EmptyArchitect {
     fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::default(),
             rooms: Vec::new(),
             spawn_locations: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
             theme: DungeonTheme::new(),
         };
         mb.fill(TileType::Floor);
-        mb.player_start = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        mb.take_snapshot();
+        mb.player_start = Point::new(mb.map.width / 2, mb.map.height / 2);
         mb.amulet_start = mb.find_most_distant();
 
         for _ in 0..50 {
             mb.spawn_locations.push(SpawnLocation {
-                point: Point::new(rng.range(1, SCREEN_WIDTH), rng.range(1, SCREEN_WIDTH)),
+                point: Point::new(rng.range(1, mb.map.width), rng.range(1, mb.map.height)),
                 preferred_entity: None,
+                template: None,
             })
         }
         mb
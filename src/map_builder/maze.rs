@@ -0,0 +1,133 @@
+use super::{themes::DungeonTheme, MapArchitect};
+use crate::prelude::*;
+
+/// Both odd, so that cells sit on odd coordinates with a wall all round.
+const WIDTH: i32 = 79;
+const HEIGHT: i32 = 49;
+const CELLS_PER_SNAPSHOT: usize = 40;
+
+/// Carves a perfect maze, one with exactly one route between any two
+/// points, using a randomised depth-first search over a grid of cells.
+pub struct MazeArchitect {}
+
+impl MapArchitect for MazeArchitect {
+    fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(WIDTH, HEIGHT),
+            rooms: Vec::new(),
+            spawn_locations: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
+            theme: DungeonTheme::new(),
+        };
+
+        mb.fill(TileType::Wall);
+        self.carve(&mut mb, rng);
+        let start = Point::new(1, 1);
+        mb.spawn_locations = mb.get_spawn_plan(&start, rng);
+        mb.player_start = start;
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+}
+
+impl MazeArchitect {
+    /// Carves the maze, taking a snapshot every `CELLS_PER_SNAPSHOT` cells.
+    fn carve(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        let cells_wide = (mb.map.width - 1) / 2;
+        let cells_high = (mb.map.height - 1) / 2;
+        let cell_to_point = |cell: Point| Point::new(cell.x * 2 + 1, cell.y * 2 + 1);
+        let mut visited = vec![false; (cells_wide * cells_high) as usize];
+        let cell_idx = |cell: Point| (cell.y * cells_wide + cell.x) as usize;
+
+        let mut stack = vec![Point::zero()];
+        visited[0] = true;
+        let idx = mb.map.point2d_to_index(cell_to_point(Point::zero()));
+        mb.map.tiles[idx] = TileType::Floor;
+        let mut carved = 1;
+
+        while let Some(cell) = stack.last().copied() {
+            let unvisited: Vec<Point> = [
+                Point::new(-1, 0),
+                Point::new(1, 0),
+                Point::new(0, -1),
+                Point::new(0, 1),
+            ]
+            .iter()
+            .map(|delta| cell + *delta)
+            .filter(|next| {
+                next.x >= 0
+                    && next.x < cells_wide
+                    && next.y >= 0
+                    && next.y < cells_high
+                    && !visited[cell_idx(*next)]
+            })
+            .collect();
+
+            match rng.random_slice_entry(&unvisited) {
+                Some(next) => {
+                    let from = cell_to_point(cell);
+                    let to = cell_to_point(*next);
+                    let between = Point::new((from.x + to.x) / 2, (from.y + to.y) / 2);
+                    for pt in [between, to] {
+                        let idx = mb.map.point2d_to_index(pt);
+                        mb.map.tiles[idx] = TileType::Floor;
+                    }
+                    visited[cell_idx(*next)] = true;
+                    stack.push(*next);
+                    carved += 1;
+                    if carved % CELLS_PER_SNAPSHOT == 0 {
+                        mb.take_snapshot();
+                    }
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+        mb.take_snapshot();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_carve_a_single_route_between_any_two_cells() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let mb = MazeArchitect {}.create_map_builder(&mut rng);
+
+        let floor: Vec<Point> = mb
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| mb.map.index_to_point2d(idx))
+            .collect();
+        let links: usize = floor
+            .iter()
+            .map(|pt| {
+                [Point::new(1, 0), Point::new(0, 1)]
+                    .iter()
+                    .filter(|delta| mb.map.can_enter_tile(*pt + **delta))
+                    .count()
+            })
+            .sum();
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+
+        assert_eq!(links, floor.len() - 1);
+        assert!(floor
+            .iter()
+            .all(|pt| dijkstra_map.map[mb.map.point2d_to_index(*pt)] < f32::MAX));
+    }
+}
@@ -1,18 +1,108 @@
 mod automata;
+mod bsp;
+mod dla;
 mod drunkard;
 mod empty;
+mod maze;
+pub mod pipeline;
 mod prefab;
 mod rooms;
+mod snapshots;
 mod themes;
+mod validate;
+mod vault;
+mod voronoi;
 
 use crate::{prelude::*, spawner::template::EntityType};
 
 use self::{
-    prefab::apply_prefab,
-    themes::{DungeonTheme, ForestTheme},
+    automata::CellularAutomataArchitect,
+    bsp::BspArchitect,
+    dla::DlaArchitect,
+    drunkard::DrunkardsWalkArchitect,
+    maze::MazeArchitect,
+    pipeline::{
+        CellularSmoothing, CullUnreachable, ExitPosition, MapPipeline, Mirror, PlaceExit,
+        PlaceStart, PlanSpawns, StartPosition, Symmetry,
+    },
+    prefab::{load_prefabs, PREFAB_DIR},
+    rooms::RoomsArchitect,
+    themes::{CavernsTheme, CryptTheme, DungeonTheme, FloodedRuinsTheme, ForestTheme},
+    voronoi::VoronoiArchitect,
 };
 
+pub use self::validate::MapProblem;
+
 const NUM_ROOMS: usize = 20;
+const NUM_TRAPS: usize = 6;
+const NUM_LIGHTS: usize = 6;
+/// How close together braziers and other lights may be placed.
+const LIGHT_SPACING: f32 = 10.0;
+const NUM_POOLS: usize = 3;
+const NUM_LAVA_SEAMS: usize = 1;
+const NUM_RUBBLE: usize = 8;
+/// How many levels to generate before settling for one that failed
+/// validation.
+const MAX_ATTEMPTS: usize = 10;
+
+/// The architects that can be asked for by name, as the map generator tool
+/// does.
+pub const ARCHITECT_NAMES: &[&str] = &[
+    "rooms", "bsp", "maze", "voronoi", "dla", "drunkard", "automata", "symmetric",
+];
+/// The themes that can be asked for by name.
+pub const THEME_NAMES: &[&str] = &["dungeon", "forest", "caverns", "crypt", "flooded_ruins"];
+
+pub fn architect_named(name: &str) -> Option<Box<dyn MapArchitect>> {
+    let architect: Box<dyn MapArchitect> = match name {
+        "rooms" => Box::new(RoomsArchitect {}),
+        "bsp" => Box::new(BspArchitect {}),
+        "maze" => Box::new(MazeArchitect {}),
+        "voronoi" => Box::new(VoronoiArchitect {}),
+        "dla" => Box::new(DlaArchitect {}),
+        "drunkard" => Box::new(DrunkardsWalkArchitect {}),
+        "automata" => Box::new(CellularAutomataArchitect {}),
+        "symmetric" => Box::new(
+            MapPipeline::new(Box::new(CellularAutomataArchitect {}))
+                .with(Mirror(Symmetry::Both))
+                .with(CellularSmoothing { iterations: 1 })
+                .with(PlaceStart(StartPosition::Center))
+                .with(CullUnreachable)
+                .with(PlaceExit(ExitPosition::MostDistant))
+                .with(PlanSpawns),
+        ),
+        _ => return None,
+    };
+    Some(architect)
+}
+
+pub fn theme_named(name: &str) -> Option<Box<dyn MapTheme>> {
+    match name {
+        "dungeon" => Some(DungeonTheme::new()),
+        "forest" => Some(ForestTheme::new()),
+        "caverns" => Some(CavernsTheme::new()),
+        "crypt" => Some(CryptTheme::new()),
+        "flooded_ruins" => Some(FloodedRuinsTheme::new()),
+        _ => None,
+    }
+}
+
+/// Picks one of the named architects, each `weight` times as likely as an
+/// architect of weight one.
+fn choose_architect(
+    weights: &[(&str, i32)],
+    rng: &mut RandomNumberGenerator,
+) -> Box<dyn MapArchitect> {
+    let total: i32 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.range(0, total);
+    for (name, weight) in weights {
+        if roll < *weight {
+            return architect_named(name).expect("Unknown architect in theme");
+        }
+        roll -= weight;
+    }
+    Box::new(RoomsArchitect {})
+}
 
 pub trait MapArchitect {
     fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
@@ -24,25 +114,65 @@ pub struct MapBuilder {
     pub spawn_locations: Vec<SpawnLocation>,
     pub player_start: Point,
     pub amulet_start: Point,
+    pub key_start: Option<Point>,
     pub theme: Box<dyn MapTheme>,
+    /// The map as it stood after each step of generating it.
+    pub history: Vec<Map>,
 }
 
 pub struct SpawnLocation {
     pub point: Point,
     pub preferred_entity: Option<EntityType>,
+    /// The name of a specific template to spawn here, such as the guard a
+    /// prefab places at its gate.
+    pub template: Option<String>,
 }
 
 impl MapBuilder {
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
-        let theme = match rng.range(0, 2) {
-            0 => DungeonTheme::new(),
-            _ => ForestTheme::new(),
-        };
-        let mut architect = theme.get_architect();
-        let mut mb = architect.create_map_builder(rng);
-        apply_prefab(&mut mb, rng);
-        mb.theme = theme;
-        mb
+    pub fn new(rng: &mut RandomNumberGenerator, level: usize) -> Self {
+        let name = THEME_NAMES[rng.range(0, THEME_NAMES.len())];
+        let theme = theme_named(name).unwrap();
+        Self::with_theme(rng, level, theme, None)
+    }
+
+    /// Builds a level in the given theme, using the named architect rather
+    /// than one of the theme's own when there is one.
+    pub fn with_theme(
+        rng: &mut RandomNumberGenerator,
+        level: usize,
+        theme: Box<dyn MapTheme>,
+        architect: Option<&str>,
+    ) -> Self {
+        let prefabs = load_prefabs(PREFAB_DIR);
+        let mut attempts = 1;
+        loop {
+            let initial = architect
+                .and_then(architect_named)
+                .unwrap_or_else(|| theme.get_architect(rng));
+            let mut pipeline = MapPipeline::for_level(initial, level, &prefabs);
+            let mut mb = pipeline.create_map_builder(rng);
+            if mb.validate().is_ok() || attempts == MAX_ATTEMPTS {
+                mb.theme = theme;
+                return mb;
+            }
+            attempts += 1;
+        }
+    }
+
+    /// A builder around an existing map with nothing else planned yet, for
+    /// tests to set up just what they need.
+    #[cfg(test)]
+    fn with_map(map: Map, player_start: Point, amulet_start: Point) -> Self {
+        Self {
+            map,
+            rooms: Vec::new(),
+            spawn_locations: Vec::new(),
+            player_start,
+            amulet_start,
+            key_start: None,
+            theme: DungeonTheme::new(),
+            history: Vec::new(),
+        }
     }
     fn fill(&mut self, tile: TileType) {
         self.map.tiles.iter_mut().for_each(|t| *t = tile);
@@ -50,8 +180,8 @@ impl MapBuilder {
 
     fn find_most_distant(&self) -> Point {
         let dijkstra_map = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            self.map.width,
+            self.map.height,
             &[self.map.point2d_to_index(self.player_start)],
             &self.map,
             1024.0,
@@ -74,8 +204,8 @@ impl MapBuilder {
     fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator) {
         while self.rooms.len() < NUM_ROOMS {
             let room = Rect::with_size(
-                rng.range(1, SCREEN_WIDTH - 10),
-                rng.range(1, SCREEN_HEIGHT - 10),
+                rng.range(1, self.map.width - 10),
+                rng.range(1, self.map.height - 10),
                 rng.range(2, 10),
                 rng.range(2, 10),
             );
@@ -87,8 +217,8 @@ impl MapBuilder {
             }
             if !overlap {
                 room.for_each(|p| {
-                    if p.x > 0 && p.x < SCREEN_WIDTH && p.y > 0 && p.y < SCREEN_HEIGHT {
-                        let idx = map_idx(p.x, p.y);
+                    if p.x > 0 && p.x < self.map.width && p.y > 0 && p.y < self.map.height {
+                        let idx = self.map.map_idx(p.x, p.y);
                         self.map.tiles[idx] = TileType::Floor;
                     }
                 });
@@ -99,7 +229,7 @@ impl MapBuilder {
 
     fn build_corridors(&mut self, rng: &mut RandomNumberGenerator) {
         let mut rooms = self.rooms.clone();
-        rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|a| a.center().x);
 
         for (i, room) in rooms.iter().enumerate().skip(1) {
             let prev = rooms[i - 1].center();
@@ -133,6 +263,248 @@ impl MapBuilder {
         }
     }
 
+    /// Scatters water pools, lava and rubble over open floor. Only a pool's
+    /// centre is too deep to wade, and only where it can be walked around,
+    /// so none of it can cut the level in two.
+    fn add_terrain(&mut self, rng: &mut RandomNumberGenerator) {
+        let mut candidates: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pt| {
+                DistanceAlg::Pythagoras.distance2d(self.player_start, *pt) > 4.0
+                    && DistanceAlg::Pythagoras.distance2d(self.amulet_start, *pt) > 2.0
+            })
+            .collect();
+        let mut pick = |rng: &mut RandomNumberGenerator| {
+            rng.random_slice_index(&candidates)
+                .map(|idx| candidates.remove(idx))
+        };
+
+        let mut pools = Vec::new();
+        for _ in 0..NUM_POOLS {
+            if let Some(center) = pick(rng) {
+                pools.push(center);
+            }
+        }
+        let mut seams = Vec::new();
+        for _ in 0..NUM_LAVA_SEAMS {
+            if let Some(center) = pick(rng) {
+                seams.push(center);
+            }
+        }
+        let mut rubble = Vec::new();
+        for _ in 0..NUM_RUBBLE {
+            if let Some(pt) = pick(rng) {
+                rubble.push(pt);
+            }
+        }
+
+        pools.iter().for_each(|center| {
+            let deep = self.is_open_ground(*center);
+            self.paint_floor(*center, 2.0, TileType::ShallowWater);
+            let idx = self.map.map_idx(center.x, center.y);
+            if deep && self.map.tiles[idx] == TileType::ShallowWater {
+                self.map.tiles[idx] = TileType::DeepWater;
+            }
+        });
+        seams
+            .iter()
+            .for_each(|center| self.paint_floor(*center, 1.0, TileType::Lava));
+        rubble
+            .iter()
+            .for_each(|pt| self.paint_floor(*pt, 0.0, TileType::Rubble));
+    }
+
+    fn is_open_ground(&self, center: Point) -> bool {
+        (-1..=1).all(|dy| {
+            (-1..=1).all(|dx| {
+                self.map
+                    .try_idx(center + Point::new(dx, dy))
+                    .map(|idx| self.map.tiles[idx] == TileType::Floor)
+                    .unwrap_or(false)
+            })
+        })
+    }
+
+    fn paint_floor(&mut self, center: Point, radius: f32, tile: TileType) {
+        let r = radius as i32;
+        for y in center.y - r..=center.y + r {
+            for x in center.x - r..=center.x + r {
+                let pt = Point::new(x, y);
+                if pt == self.player_start
+                    || pt == self.amulet_start
+                    || self.key_start == Some(pt)
+                    || self.spawn_locations.iter().any(|s| s.point == pt)
+                {
+                    continue;
+                }
+                if let Some(idx) = self.map.try_idx(pt) {
+                    if self.map.tiles[idx] == TileType::Floor
+                        && DistanceAlg::Pythagoras.distance2d(center, pt) <= radius
+                    {
+                        self.map.tiles[idx] = tile;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sets aside a few floor tiles, away from the start and the amulet,
+    /// for hidden traps.
+    fn plan_traps(&mut self, rng: &mut RandomNumberGenerator) {
+        let mut candidates: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pt| {
+                DistanceAlg::Pythagoras.distance2d(self.player_start, *pt) > 5.0
+                    && *pt != self.amulet_start
+                    && self.spawn_locations.iter().all(|spawn| spawn.point != *pt)
+            })
+            .collect();
+
+        for _ in 0..NUM_TRAPS {
+            let target_index = match rng.random_slice_index(&candidates) {
+                Some(idx) => idx,
+                None => break,
+            };
+            self.spawn_locations.push(SpawnLocation {
+                point: candidates.remove(target_index),
+                preferred_entity: Some(EntityType::Trap),
+                template: None,
+            });
+        }
+    }
+
+    /// Sets aside a few floor tiles against the walls, spread out from one
+    /// another, for braziers or whatever else lights the theme's levels.
+    fn plan_lights(&mut self, rng: &mut RandomNumberGenerator) {
+        let mut candidates: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pt| {
+                let against_wall = [
+                    Point::new(-1, 0),
+                    Point::new(1, 0),
+                    Point::new(0, -1),
+                    Point::new(0, 1),
+                ]
+                .iter()
+                .any(|delta| {
+                    self.map
+                        .try_idx(*pt + *delta)
+                        .is_some_and(|idx| self.map.tiles[idx] == TileType::Wall)
+                });
+                against_wall
+                    && *pt != self.player_start
+                    && *pt != self.amulet_start
+                    && self.key_start != Some(*pt)
+                    && self.spawn_locations.iter().all(|spawn| spawn.point != *pt)
+            })
+            .collect();
+
+        for _ in 0..NUM_LIGHTS {
+            let light = match rng.random_slice_index(&candidates) {
+                Some(idx) => candidates.remove(idx),
+                None => break,
+            };
+            candidates.retain(|pt| DistanceAlg::Pythagoras.distance2d(light, *pt) > LIGHT_SPACING);
+            self.spawn_locations.push(SpawnLocation {
+                point: light,
+                preferred_entity: Some(EntityType::Prop),
+                template: None,
+            });
+        }
+    }
+
+    /// Puts a door wherever a corridor passes through the wall around a room.
+    fn build_doors(&mut self) {
+        let rooms = self.rooms.clone();
+        for room in rooms.iter() {
+            for y in room.y1 - 1..=room.y2 {
+                for x in room.x1 - 1..=room.x2 {
+                    let on_wall =
+                        x == room.x1 - 1 || x == room.x2 || y == room.y1 - 1 || y == room.y2;
+                    let pt = Point::new(x, y);
+                    if on_wall && self.is_doorway(pt) {
+                        let idx = self.map.map_idx(x, y);
+                        self.map.tiles[idx] = TileType::Door;
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_doorway(&self, pt: Point) -> bool {
+        let is = |delta: Point, tile: TileType| {
+            self.map
+                .try_idx(pt + delta)
+                .map(|idx| self.map.tiles[idx] == tile)
+                .unwrap_or(false)
+        };
+        let floor = |delta| is(delta, TileType::Floor);
+        let wall = |delta| is(delta, TileType::Wall);
+        let across_vertical_corridor = wall(Point::new(-1, 0))
+            && wall(Point::new(1, 0))
+            && floor(Point::new(0, -1))
+            && floor(Point::new(0, 1));
+        let across_horizontal_corridor = wall(Point::new(0, -1))
+            && wall(Point::new(0, 1))
+            && floor(Point::new(-1, 0))
+            && floor(Point::new(1, 0));
+        floor(Point::zero()) && (across_vertical_corridor || across_horizontal_corridor)
+    }
+
+    /// Locks one of the doors and leaves its key somewhere that can be reached
+    /// without going through it.
+    fn lock_a_door(&mut self, rng: &mut RandomNumberGenerator) {
+        let doors: Vec<usize> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Door)
+            .map(|(idx, _)| idx)
+            .collect();
+        let door = match rng.random_slice_entry(&doors) {
+            Some(door) => *door,
+            None => return,
+        };
+        self.map.tiles[door] = TileType::LockedDoor;
+
+        let dijkstra_map = DijkstraMap::new(
+            self.map.width,
+            self.map.height,
+            &[self.map.point2d_to_index(self.player_start)],
+            &self.map,
+            1024.0,
+        );
+        let reachable: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(idx, t)| **t == TileType::Floor && dijkstra_map.map[*idx] < f32::MAX)
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pt| *pt != self.player_start && *pt != self.amulet_start)
+            .collect();
+        match rng.random_slice_entry(&reachable) {
+            Some(key) => self.key_start = Some(*key),
+            None => self.map.tiles[door] = TileType::Door,
+        }
+    }
+
     fn get_spawn_plan(&self, start: &Point, rng: &mut RandomNumberGenerator) -> Vec<SpawnLocation> {
         const NUM_MONSTERS: usize = 50;
         let mut spawnable_tiles: Vec<Point> = self
@@ -150,10 +522,14 @@ impl MapBuilder {
 
         let mut spawns = Vec::new();
         for _ in 0..NUM_MONSTERS {
-            let target_index = rng.random_slice_index(&spawnable_tiles).unwrap();
+            let target_index = match rng.random_slice_index(&spawnable_tiles) {
+                Some(idx) => idx,
+                None => break,
+            };
             spawns.push(SpawnLocation {
-                point: spawnable_tiles[target_index].clone(),
+                point: spawnable_tiles[target_index],
                 preferred_entity: None,
+                template: None,
             });
             spawnable_tiles.remove(target_index);
         }
@@ -162,6 +538,138 @@ impl MapBuilder {
 }
 
 pub trait MapTheme: Sync + Send {
+    /// What the theme is called in `template.ron` and on the command line.
+    fn name(&self) -> &'static str;
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
-    fn get_architect(&self) -> Box<dyn MapArchitect>;
+    fn tile_description(&self, tile_type: TileType) -> &'static str;
+    fn tile_tint(&self, tile_type: TileType) -> RGB;
+
+    /// The colour of tiles the player has seen but can't see now.
+    fn remembered_tint(&self) -> RGB {
+        DARK_GRAY.into()
+    }
+
+    /// How far the player can see.
+    fn visibility_radius(&self) -> i32 {
+        8
+    }
+
+    /// How brightly lit the level is away from any torch or brazier, from
+    /// 0.0 for pitch black to 1.0 for the full colour of each tile.
+    fn ambient_light(&self) -> f32 {
+        0.6
+    }
+
+    /// The architects levels in this theme are built by, with how likely
+    /// each is to be picked.
+    fn architect_weights(&self) -> &'static [(&'static str, i32)];
+
+    fn get_architect(&self, rng: &mut RandomNumberGenerator) -> Box<dyn MapArchitect> {
+        choose_architect(self.architect_weights(), rng)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_put_a_door_where_a_corridor_enters_a_room() {
+        let mut mb = MapBuilder::with_map(Map::default(), Point::new(12, 12), Point::new(25, 12));
+        mb.rooms = vec![Rect::with_size(10, 10, 5, 5)];
+        mb.fill(TileType::Wall);
+        mb.rooms[0].for_each(|pt| {
+            let idx = mb.map.map_idx(pt.x, pt.y);
+            mb.map.tiles[idx] = TileType::Floor;
+        });
+        mb.apply_horizontal_tunnel(12, 25, 12);
+
+        mb.build_doors();
+
+        assert_eq!(mb.map.tiles[mb.map.map_idx(15, 12)], TileType::Door);
+        assert_eq!(mb.map.tiles[mb.map.map_idx(16, 12)], TileType::Floor);
+        assert_eq!(
+            mb.map.tiles.iter().filter(|t| **t == TileType::Door).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn should_keep_every_tile_reachable_after_adding_terrain() {
+        let mut mb = MapBuilder::with_map(Map::default(), Point::new(11, 11), Point::new(28, 20));
+        mb.rooms = vec![Rect::with_size(10, 10, 20, 12)];
+        mb.fill(TileType::Wall);
+        mb.rooms[0].for_each(|pt| {
+            let idx = mb.map.map_idx(pt.x, pt.y);
+            mb.map.tiles[idx] = TileType::Floor;
+        });
+        let mut rng = RandomNumberGenerator::seeded(3);
+
+        mb.add_terrain(&mut rng);
+
+        assert!(mb.map.tiles.contains(&TileType::ShallowWater));
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+        mb.rooms[0].for_each(|pt| {
+            let idx = mb.map.map_idx(pt.x, pt.y);
+            if mb.map.tiles[idx] != TileType::DeepWater {
+                assert!(dijkstra_map.map[idx] < f32::MAX);
+            }
+        });
+    }
+
+    #[test]
+    fn should_leave_the_key_on_the_near_side_of_a_locked_door() {
+        let mut mb = MapBuilder::with_map(Map::default(), Point::new(12, 12), Point::new(25, 12));
+        mb.rooms = vec![Rect::with_size(10, 10, 5, 5)];
+        mb.fill(TileType::Wall);
+        mb.rooms[0].for_each(|pt| {
+            let idx = mb.map.map_idx(pt.x, pt.y);
+            mb.map.tiles[idx] = TileType::Floor;
+        });
+        mb.apply_horizontal_tunnel(12, 25, 12);
+        mb.build_doors();
+        let mut rng = RandomNumberGenerator::seeded(1);
+
+        mb.lock_a_door(&mut rng);
+
+        assert_eq!(mb.map.tiles[mb.map.map_idx(15, 12)], TileType::LockedDoor);
+        let key = mb.key_start.unwrap();
+        assert!(key.x < 15);
+    }
+
+    #[test]
+    fn should_plan_fewer_spawns_when_little_floor_qualifies() {
+        let mut mb = MapBuilder::with_map(Map::default(), Point::new(12, 12), Point::new(42, 12));
+        mb.rooms = vec![Rect::with_size(10, 10, 5, 5), Rect::with_size(40, 10, 5, 5)];
+        mb.fill(TileType::Wall);
+        mb.rooms[1].for_each(|pt| {
+            let idx = mb.map.map_idx(pt.x, pt.y);
+            mb.map.tiles[idx] = TileType::Floor;
+        });
+        let mut rng = RandomNumberGenerator::seeded(1);
+
+        let spawns = mb.get_spawn_plan(&mb.player_start, &mut rng);
+
+        assert_eq!(spawns.len(), 25);
+    }
+
+    #[test]
+    fn should_build_the_same_level_from_the_same_seed() {
+        let build = || {
+            let mut rng = RandomNumberGenerator::seeded(42);
+            MapBuilder::new(&mut rng, 1)
+        };
+
+        let (first, second) = (build(), build());
+
+        assert_eq!(first.map.tiles, second.map.tiles);
+        assert_eq!(first.player_start, second.player_start);
+        assert_eq!(first.amulet_start, second.amulet_start);
+    }
 }
@@ -0,0 +1,379 @@
+use super::{
+    automata::CellularAutomataArchitect,
+    prefab::{apply_prefabs, Prefab},
+    MapArchitect,
+};
+use crate::prelude::*;
+
+/// One step in a `MapPipeline`, reshaping whatever the steps before it built.
+pub trait MapModifier {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator);
+}
+
+/// An architect followed by any number of modifiers, run in order. A
+/// pipeline is itself an architect, so themes can hand one out in place of
+/// a dedicated architect struct.
+pub struct MapPipeline {
+    initial: Box<dyn MapArchitect>,
+    modifiers: Vec<Box<dyn MapModifier>>,
+}
+
+impl MapPipeline {
+    pub fn new(initial: Box<dyn MapArchitect>) -> Self {
+        Self {
+            initial,
+            modifiers: Vec::new(),
+        }
+    }
+
+    /// The steps every level goes through after its architect: older levels
+    /// have more crumbling rooms, and all of them get prefabs, terrain, traps
+    /// and lights.
+    pub fn for_level(initial: Box<dyn MapArchitect>, level: usize, prefabs: &[Prefab]) -> Self {
+        let pipeline = Self::new(initial);
+        let pipeline = if level > 0 {
+            pipeline.with(RoomEdgeErosion {
+                chance: 10 * level as i32,
+            })
+        } else {
+            pipeline
+        };
+        pipeline
+            .with(PrefabStamping {
+                prefabs: prefabs.to_vec(),
+                level,
+            })
+            .with(AddTerrain)
+            .with(PlanTraps)
+            .with(PlanLights)
+    }
+
+    pub fn with(mut self, modifier: impl MapModifier + 'static) -> Self {
+        self.modifiers.push(Box::new(modifier));
+        self
+    }
+}
+
+impl MapArchitect for MapPipeline {
+    fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = self.initial.create_map_builder(rng);
+        self.modifiers.iter_mut().for_each(|modifier| {
+            modifier.modify(&mut mb, rng);
+            mb.take_snapshot();
+        });
+        mb
+    }
+}
+
+/// Runs the cellular automata rule over the map, rounding off corners and
+/// filling in stray walls.
+pub struct CellularSmoothing {
+    pub iterations: usize,
+}
+
+impl MapModifier for CellularSmoothing {
+    fn modify(&mut self, mb: &mut MapBuilder, _rng: &mut RandomNumberGenerator) {
+        let mut automata = CellularAutomataArchitect {};
+        for _ in 0..self.iterations {
+            automata.iteration(&mut mb.map);
+        }
+    }
+}
+
+/// Knocks away some of the wall around each room, as a percentage chance
+/// per tile.
+pub struct RoomEdgeErosion {
+    pub chance: i32,
+}
+
+impl MapModifier for RoomEdgeErosion {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        let rooms = mb.rooms.clone();
+        for room in rooms.iter() {
+            for y in room.y1 - 1..=room.y2 {
+                for x in room.x1 - 1..=room.x2 {
+                    let on_wall =
+                        x == room.x1 - 1 || x == room.x2 || y == room.y1 - 1 || y == room.y2;
+                    let on_map_edge =
+                        x <= 0 || y <= 0 || x >= mb.map.width - 1 || y >= mb.map.height - 1;
+                    if !on_wall || on_map_edge {
+                        continue;
+                    }
+                    let idx = mb.map.map_idx(x, y);
+                    if mb.map.tiles[idx] == TileType::Wall && rng.range(0, 100) < self.chance {
+                        mb.map.tiles[idx] = TileType::Floor;
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct PrefabStamping {
+    pub prefabs: Vec<Prefab>,
+    pub level: usize,
+}
+
+impl MapModifier for PrefabStamping {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        apply_prefabs(mb, &self.prefabs, self.level, rng);
+    }
+}
+
+pub struct AddTerrain;
+
+impl MapModifier for AddTerrain {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        mb.add_terrain(rng);
+    }
+}
+
+/// Floods the level with extra pools of shallow water, away from the start.
+pub struct Flood {
+    pub pools: usize,
+}
+
+impl MapModifier for Flood {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        let floor: Vec<Point> = mb
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| mb.map.index_to_point2d(idx))
+            .filter(|pt| DistanceAlg::Pythagoras.distance2d(mb.player_start, *pt) > 4.0)
+            .collect();
+        for _ in 0..self.pools {
+            if let Some(center) = rng.random_slice_entry(&floor) {
+                mb.paint_floor(*center, 3.0, TileType::ShallowWater);
+            }
+        }
+    }
+}
+
+pub struct PlanTraps;
+
+impl MapModifier for PlanTraps {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        mb.plan_traps(rng);
+    }
+}
+
+pub struct PlanLights;
+
+impl MapModifier for PlanLights {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        mb.plan_lights(rng);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+/// Copies the left half of the map onto the right, the top onto the bottom,
+/// or both. Start, exit and spawns should be placed again afterwards.
+pub struct Mirror(pub Symmetry);
+
+impl MapModifier for Mirror {
+    fn modify(&mut self, mb: &mut MapBuilder, _rng: &mut RandomNumberGenerator) {
+        let map = &mut mb.map;
+        if matches!(self.0, Symmetry::Horizontal | Symmetry::Both) {
+            for y in 0..map.height {
+                for x in map.width / 2..map.width {
+                    let from = map.map_idx(map.width - 1 - x, y);
+                    let to = map.map_idx(x, y);
+                    map.tiles[to] = map.tiles[from];
+                }
+            }
+        }
+        if matches!(self.0, Symmetry::Vertical | Symmetry::Both) {
+            for y in map.height / 2..map.height {
+                for x in 0..map.width {
+                    let from = map.map_idx(x, map.height - 1 - y);
+                    let to = map.map_idx(x, y);
+                    map.tiles[to] = map.tiles[from];
+                }
+            }
+        }
+    }
+}
+
+/// Walls in any floor that can't be reached from the player's start and
+/// drops the spawn locations that were on it.
+pub struct CullUnreachable;
+
+impl MapModifier for CullUnreachable {
+    fn modify(&mut self, mb: &mut MapBuilder, _rng: &mut RandomNumberGenerator) {
+        let reachable = mb.reachable();
+        mb.map
+            .tiles
+            .iter_mut()
+            .zip(reachable.iter())
+            .filter(|(t, reached)| **t == TileType::Floor && !**reached)
+            .for_each(|(t, _)| *t = TileType::Wall);
+        let map = &mb.map;
+        mb.spawn_locations
+            .retain(|spawn| map.try_idx(spawn.point).is_some_and(|idx| reachable[idx]));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartPosition {
+    Center,
+    TopLeft,
+    RandomFloor,
+}
+
+/// Moves the player's start to the floor tile closest to the chosen spot.
+pub struct PlaceStart(pub StartPosition);
+
+impl MapModifier for PlaceStart {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        let floor: Vec<Point> = mb
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| mb.map.index_to_point2d(idx))
+            .collect();
+        let target = match self.0 {
+            StartPosition::Center => Point::new(mb.map.width / 2, mb.map.height / 2),
+            StartPosition::TopLeft => Point::zero(),
+            StartPosition::RandomFloor => match rng.random_slice_entry(&floor) {
+                Some(pt) => *pt,
+                None => return,
+            },
+        };
+        if let Some(start) = floor.iter().min_by(|a, b| {
+            let distance = |pt: &Point| DistanceAlg::Pythagoras.distance2d(target, *pt);
+            distance(a).partial_cmp(&distance(b)).unwrap()
+        }) {
+            mb.player_start = *start;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitPosition {
+    MostDistant,
+    RandomFloor,
+}
+
+/// Moves the amulet, or the stairs down, relative to the player's start.
+pub struct PlaceExit(pub ExitPosition);
+
+impl MapModifier for PlaceExit {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        mb.amulet_start = match self.0 {
+            ExitPosition::MostDistant => mb.find_most_distant(),
+            ExitPosition::RandomFloor => {
+                let reachable = mb.reachable();
+                let floor: Vec<Point> = mb
+                    .map
+                    .tiles
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, t)| **t == TileType::Floor && reachable[*idx])
+                    .map(|(idx, _)| mb.map.index_to_point2d(idx))
+                    .filter(|pt| *pt != mb.player_start)
+                    .collect();
+                match rng.random_slice_entry(&floor) {
+                    Some(pt) => *pt,
+                    None => return,
+                }
+            }
+        };
+    }
+}
+
+/// Throws away the spawn plan and scatters a new one away from the start.
+pub struct PlanSpawns;
+
+impl MapModifier for PlanSpawns {
+    fn modify(&mut self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+        mb.spawn_locations = mb.get_spawn_plan(&mb.player_start, rng);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::empty::EmptyArchitect;
+    use super::*;
+
+    fn level(width: i32, height: i32) -> MapBuilder {
+        MapBuilder::with_map(Map::new(width, height), Point::new(1, 1), Point::new(1, 1))
+    }
+
+    #[test]
+    fn should_mirror_the_left_half_onto_the_right() {
+        let mut mb = level(10, 4);
+        let idx = mb.map.map_idx(1, 2);
+        mb.map.tiles[idx] = TileType::Wall;
+        let mut rng = RandomNumberGenerator::seeded(1);
+
+        Mirror(Symmetry::Horizontal).modify(&mut mb, &mut rng);
+
+        assert_eq!(mb.map.tiles[mb.map.map_idx(8, 2)], TileType::Wall);
+        assert_eq!(
+            mb.map
+                .tiles
+                .iter()
+                .filter(|t| **t == TileType::Wall)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn should_cull_floor_that_cannot_be_reached() {
+        let mut mb = level(10, 4);
+        for y in 0..4 {
+            let idx = mb.map.map_idx(5, y);
+            mb.map.tiles[idx] = TileType::Wall;
+        }
+        mb.spawn_locations.push(SpawnLocation {
+            point: Point::new(8, 2),
+            preferred_entity: None,
+            template: None,
+        });
+        let mut rng = RandomNumberGenerator::seeded(1);
+
+        CullUnreachable.modify(&mut mb, &mut rng);
+
+        assert_eq!(
+            mb.map
+                .tiles
+                .iter()
+                .filter(|t| **t == TileType::Floor)
+                .count(),
+            20
+        );
+        assert!(mb.spawn_locations.is_empty());
+    }
+
+    #[test]
+    fn should_run_modifiers_in_order_after_the_architect() {
+        let mut pipeline = MapPipeline::new(Box::new(EmptyArchitect {}))
+            .with(PlaceStart(StartPosition::TopLeft))
+            .with(PlaceExit(ExitPosition::MostDistant))
+            .with(PlanSpawns);
+        let mut rng = RandomNumberGenerator::seeded(1);
+
+        let mb = pipeline.create_map_builder(&mut rng);
+
+        assert_eq!(mb.player_start, Point::zero());
+        assert_eq!(
+            mb.amulet_start,
+            Point::new(mb.map.width - 1, mb.map.height - 1)
+        );
+        assert!(mb.spawn_locations.iter().all(|spawn| {
+            DistanceAlg::Pythagoras.distance2d(mb.player_start, spawn.point) > 10.0
+        }));
+    }
+}
@@ -1,93 +1,297 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+
+use ron::de::from_reader;
+use serde::Deserialize;
+
 use crate::{prelude::*, spawner::template::EntityType};
 
-const FORTRESS: (&str, i32, i32) = (
-    "
-------------
----######---
----#----#---
----#-M--#---
--###----###-
---M------M--
--###----###-
----#----#---
----#----#---
----######---
-------------
-",
-    12,
-    11,
-);
-
-pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
-    let mut placement = None;
+pub const PREFAB_DIR: &str = "resources/prefabs";
+const MAX_PREFABS_PER_LEVEL: usize = 3;
+const MIN_DISTANCE_FROM_START: f32 = 20.0;
+
+/// A hand-drawn vault that can be stamped onto a generated level. Every
+/// character of the layout is looked up in the legend. Layouts should keep a
+/// walkable border so that stamping one can't cut the rest of the level off.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Prefab {
+    pub name: String,
+    pub levels: HashSet<usize>,
+    pub frequency: i32,
+    pub layout: Vec<String>,
+    pub legend: HashMap<char, PrefabCell>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub enum PrefabCell {
+    Tile(TileType),
+    /// Floor with a monster, item or trap rolled from the level's pool.
+    Spawn(EntityType),
+    /// Floor with the template of this name on it.
+    Named(String),
+}
+
+impl Prefab {
+    /// The layout turned `rotations` quarter turns clockwise and then, if
+    /// asked, mirrored left to right.
+    pub fn oriented(&self, rotations: i32, mirrored: bool) -> Vec<Vec<char>> {
+        let mut grid: Vec<Vec<char>> = self
+            .layout
+            .iter()
+            .map(|row| row.chars().collect())
+            .collect();
+        for _ in 0..rotations {
+            grid = rotate(&grid);
+        }
+        if mirrored {
+            grid.iter_mut().for_each(|row| row.reverse());
+        }
+        grid
+    }
+}
+
+fn rotate(grid: &[Vec<char>]) -> Vec<Vec<char>> {
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    (0..width)
+        .map(|x| {
+            grid.iter()
+                .rev()
+                .map(|row| row.get(x).copied().unwrap_or(' '))
+                .collect()
+        })
+        .collect()
+}
+
+/// Reads every `.ron` prefab in `dir`, in file name order.
+pub fn load_prefabs(dir: &str) -> Vec<Prefab> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .expect("Failed to read prefab directory")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let file = File::open(path).expect("Failed to open prefab");
+            from_reader(file).expect("Unable to load prefab")
+        })
+        .collect()
+}
+
+/// Stamps up to `MAX_PREFABS_PER_LEVEL` prefabs eligible for `level` onto
+/// the map, each in a randomly chosen orientation and wherever it fits.
+pub fn apply_prefabs(
+    mb: &mut MapBuilder,
+    prefabs: &[Prefab],
+    level: usize,
+    rng: &mut RandomNumberGenerator,
+) {
+    let mut pool = Vec::new();
+    prefabs
+        .iter()
+        .filter(|prefab| prefab.levels.contains(&level))
+        .for_each(|prefab| {
+            for _ in 0..prefab.frequency {
+                pool.push(prefab);
+            }
+        });
 
     let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        &vec![mb.map.point2d_to_index(mb.player_start)],
+        mb.map.width,
+        mb.map.height,
+        &[mb.map.point2d_to_index(mb.player_start)],
         &mb.map,
         1024.0,
     );
 
-    let mut attempts = 0;
+    let mut placed: Vec<Rect> = Vec::new();
+    for _ in 0..MAX_PREFABS_PER_LEVEL {
+        let prefab = match rng.random_slice_entry(&pool) {
+            Some(prefab) => *prefab,
+            None => break,
+        };
+        let grid = prefab.oriented(rng.range(0, 4), rng.range(0, 2) == 0);
+        let spots = find_spots(mb, &grid, &dijkstra_map, &placed);
+        if let Some(spot) = rng.random_slice_entry(&spots) {
+            placed.push(stamp(mb, prefab, &grid, *spot));
+        }
+    }
+}
 
-    while placement.is_none() && attempts < 10 {
-        let dimensions = Rect::with_size(
-            rng.range(0, SCREEN_WIDTH - FORTRESS.1),
-            rng.range(0, SCREEN_HEIGHT - FORTRESS.2),
-            FORTRESS.1,
-            FORTRESS.2,
-        );
+/// Every top-left corner at which `grid` would stay inside the map, clear of
+/// the start, the amulet, the key and other prefabs, and be reached from the
+/// start without being right next to it.
+fn find_spots(
+    mb: &MapBuilder,
+    grid: &[Vec<char>],
+    dijkstra_map: &DijkstraMap,
+    placed: &[Rect],
+) -> Vec<Point> {
+    let height = grid.len() as i32;
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+    let reserved: Vec<Point> = [Some(mb.player_start), Some(mb.amulet_start), mb.key_start]
+        .into_iter()
+        .flatten()
+        .collect();
+    let distance = |pt: Point| dijkstra_map.map[mb.map.point2d_to_index(pt)];
 
-        let mut can_place = false;
-        dimensions.for_each(|pt| {
-            let idx = mb.map.point2d_to_index(pt);
-            let distance = dijkstra_map.map[idx];
-            if distance < 2000.0 && distance > 20.0 && mb.amulet_start != pt {
-                can_place = true;
+    let mut spots = Vec::new();
+    for y in 1..mb.map.height - height {
+        for x in 1..mb.map.width - width {
+            let area = Rect::with_size(x, y, width, height);
+            if placed.iter().any(|other| other.intersect(&area))
+                || reserved.iter().any(|pt| area.point_in_rect(*pt))
+            {
+                continue;
+            }
+            let mut too_close = false;
+            let mut reached = false;
+            area.for_each(|pt| {
+                let d = distance(pt);
+                too_close |= d <= MIN_DISTANCE_FROM_START;
+                let on_border = pt.x == area.x1
+                    || pt.x == area.x2 - 1
+                    || pt.y == area.y1
+                    || pt.y == area.y2 - 1;
+                reached |= on_border && d < f32::MAX;
+            });
+            if reached && !too_close {
+                spots.push(Point::new(x, y));
             }
-        });
-
-        if can_place {
-            placement = Some(Point::new(dimensions.x1, dimensions.y1));
-            let points = dimensions.point_set();
-            mb.spawn_locations.retain(|pt| !points.contains(&pt.point));
         }
-        attempts += 1;
     }
+    spots
+}
 
-    if let Some(placement) = placement {
-        let string_vec: Vec<char> = FORTRESS
-            .0
-            .chars()
-            .filter(|a| *a != '\r' && *a != '\n')
-            .collect();
+fn stamp(mb: &mut MapBuilder, prefab: &Prefab, grid: &[Vec<char>], at: Point) -> Rect {
+    let height = grid.len() as i32;
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+    let area = Rect::with_size(at.x, at.y, width, height);
+    mb.spawn_locations
+        .retain(|spawn| !area.point_in_rect(spawn.point));
 
-        let mut i = 0;
-        for ty in placement.y..placement.y + FORTRESS.2 {
-            for tx in placement.x..placement.x + FORTRESS.1 {
-                let idx = map_idx(tx, ty);
-                let c = string_vec[i];
-                match c {
-                    'M' => {
-                        mb.map.tiles[idx] = TileType::Floor;
-                        mb.spawn_locations.push(SpawnLocation {
-                            point: Point::new(tx, ty),
-                            preferred_entity: Some(EntityType::Enemy),
-                        });
-                    }
-                    '-' => {
-                        mb.map.tiles[idx] = TileType::Floor;
-                    }
-                    '#' => {
-                        mb.map.tiles[idx] = TileType::Wall;
-                    }
-                    _ => {
-                        println!("No rendering instruction for [{}]", c)
-                    }
+    for (dy, row) in grid.iter().enumerate() {
+        for (dx, c) in row.iter().enumerate() {
+            let point = at + Point::new(dx as i32, dy as i32);
+            let idx = mb.map.map_idx(point.x, point.y);
+            let (preferred_entity, template) = match prefab.legend.get(c) {
+                Some(PrefabCell::Tile(tile)) => {
+                    mb.map.tiles[idx] = *tile;
+                    continue;
                 }
-                i += 1;
-            }
+                Some(PrefabCell::Spawn(entity_type)) => (Some(*entity_type), None),
+                Some(PrefabCell::Named(name)) => (None, Some(name.clone())),
+                None => {
+                    println!("No rendering instruction for [{}] in {}", c, prefab.name);
+                    continue;
+                }
+            };
+            mb.map.tiles[idx] = TileType::Floor;
+            mb.spawn_locations.push(SpawnLocation {
+                point,
+                preferred_entity,
+                template,
+            });
+        }
+    }
+    area
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn prefab(layout: &[&str]) -> Prefab {
+        Prefab {
+            name: "Test".to_string(),
+            levels: HashSet::from([0]),
+            frequency: 1,
+            layout: layout.iter().map(|row| row.to_string()).collect(),
+            legend: HashMap::from([
+                ('-', PrefabCell::Tile(TileType::Floor)),
+                ('#', PrefabCell::Tile(TileType::Wall)),
+                ('M', PrefabCell::Spawn(EntityType::Enemy)),
+                ('o', PrefabCell::Named("Orc".to_string())),
+            ]),
         }
     }
+
+    #[test]
+    fn should_rotate_and_mirror_layouts() {
+        let prefab = prefab(&["ab", "cd", "ef"]);
+
+        let turned = prefab.oriented(1, false);
+        let mirrored = prefab.oriented(0, true);
+
+        assert_eq!(turned, vec![vec!['e', 'c', 'a'], vec!['f', 'd', 'b']]);
+        assert_eq!(
+            mirrored,
+            vec![vec!['b', 'a'], vec!['d', 'c'], vec!['f', 'e']]
+        );
+        assert_eq!(prefab.oriented(4, false), prefab.oriented(0, false));
+    }
+
+    #[test]
+    fn should_load_every_prefab_with_a_complete_legend() {
+        let prefabs = load_prefabs(PREFAB_DIR);
+
+        assert!(!prefabs.is_empty());
+        prefabs.iter().for_each(|prefab| {
+            prefab
+                .layout
+                .iter()
+                .flat_map(|row| row.chars())
+                .for_each(|c| {
+                    assert!(
+                        prefab.legend.contains_key(&c),
+                        "{} has no '{}'",
+                        prefab.name,
+                        c
+                    );
+                });
+        });
+    }
+
+    fn open_level() -> MapBuilder {
+        MapBuilder::with_map(Map::new(60, 30), Point::new(2, 2), Point::new(57, 27))
+    }
+
+    #[test]
+    fn should_stamp_prefabs_away_from_the_start() {
+        let mut mb = open_level();
+        let prefabs = vec![prefab(&["-----", "-#M#-", "-#o#-", "-----"])];
+        let mut rng = RandomNumberGenerator::seeded(7);
+
+        apply_prefabs(&mut mb, &prefabs, 0, &mut rng);
+
+        let walls = mb
+            .map
+            .tiles
+            .iter()
+            .filter(|t| **t == TileType::Wall)
+            .count();
+        assert_eq!(walls, 4 * MAX_PREFABS_PER_LEVEL);
+        assert_eq!(mb.spawn_locations.len(), 2 * MAX_PREFABS_PER_LEVEL);
+        assert!(mb.spawn_locations.iter().all(|spawn| {
+            DistanceAlg::Pythagoras.distance2d(mb.player_start, spawn.point)
+                > MIN_DISTANCE_FROM_START
+        }));
+        assert!(mb
+            .spawn_locations
+            .iter()
+            .any(|spawn| spawn.template.as_deref() == Some("Orc")));
+    }
+
+    #[test]
+    fn should_only_use_prefabs_meant_for_the_level() {
+        let mut mb = open_level();
+        let prefabs = vec![prefab(&["-----", "-#M#-", "-----"])];
+        let mut rng = RandomNumberGenerator::seeded(7);
+
+        apply_prefabs(&mut mb, &prefabs, 1, &mut rng);
+
+        assert!(!mb.map.tiles.contains(&TileType::Wall));
+        assert!(mb.spawn_locations.is_empty());
+    }
 }
@@ -1,28 +1,41 @@
 use super::{themes::DungeonTheme, MapArchitect};
 use crate::prelude::*;
 
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 50;
+
 pub struct RoomsArchitect {}
 
 impl MapArchitect for RoomsArchitect {
     fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(WIDTH, HEIGHT),
             rooms: Vec::new(),
             spawn_locations: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
             theme: DungeonTheme::new(),
         };
 
         mb.fill(TileType::Wall);
         mb.build_random_rooms(rng);
+        mb.take_snapshot();
         mb.build_corridors(rng);
+        mb.take_snapshot();
+        mb.build_doors();
+        mb.take_snapshot();
         mb.player_start = mb.rooms[0].center();
         mb.amulet_start = mb.find_most_distant();
+        if rng.range(0, 2) == 0 {
+            mb.lock_a_door(rng);
+        }
         for room in mb.rooms.iter().skip(1) {
             mb.spawn_locations.push(SpawnLocation {
                 point: room.center(),
                 preferred_entity: None,
+                template: None,
             });
         }
 
@@ -0,0 +1,53 @@
+use std::{fs, io, path::Path};
+
+use crate::prelude::*;
+
+impl MapBuilder {
+    /// Records the map as it stands, so the steps that built it can be
+    /// replayed or inspected later.
+    pub fn take_snapshot(&mut self) {
+        self.history.push(self.map.clone());
+    }
+
+    /// Writes each snapshot to its own numbered text file in `dir`.
+    pub fn dump_history(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (step, map) in self.history.iter().enumerate() {
+            fs::write(
+                dir.join(format!("snapshot_{:03}.txt", step)),
+                map.to_ascii(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{automata::CellularAutomataArchitect, MapArchitect};
+    use super::*;
+
+    #[test]
+    fn should_record_noise_and_each_automata_iteration() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let mb = CellularAutomataArchitect {}.create_map_builder(&mut rng);
+
+        assert_eq!(mb.history.len(), 11);
+        assert_ne!(mb.history[0].tiles, mb.history[10].tiles);
+        assert_eq!(mb.history[10].tiles, mb.map.tiles);
+    }
+
+    #[test]
+    fn should_dump_one_text_file_per_snapshot() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let mb = CellularAutomataArchitect {}.create_map_builder(&mut rng);
+        let dir = std::env::temp_dir().join("mapgen_snapshot_test");
+
+        mb.dump_history(&dir).unwrap();
+
+        let last = fs::read_to_string(dir.join("snapshot_010.txt")).unwrap();
+        assert_eq!(last, mb.map.to_ascii());
+        assert_eq!(last.lines().count(), mb.map.height as usize);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,51 +1,390 @@
 use crate::prelude::*;
 
-use super::{rooms::RoomsArchitect, drunkard::DrunkardsWalkArchitect, automata::CellularAutomataArchitect};
+use super::{
+    choose_architect,
+    pipeline::{Flood, MapPipeline},
+};
+
+/// The glyphs for stone-built levels.
+fn stone_glyph(tile_type: TileType) -> FontCharType {
+    match tile_type {
+        TileType::Floor => to_cp437('.'),
+        TileType::Wall => to_cp437('#'),
+        TileType::Exit => to_cp437('>'),
+        TileType::Door => to_cp437(';'),
+        TileType::OpenDoor => to_cp437('.'),
+        TileType::LockedDoor => to_cp437(';'),
+        TileType::ShallowWater => to_cp437('~'),
+        TileType::DeepWater => to_cp437('='),
+        TileType::Lava => to_cp437('^'),
+        TileType::Rubble => to_cp437(':'),
+    }
+}
 
 pub struct DungeonTheme {}
 
 impl DungeonTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
 }
 
 impl MapTheme for DungeonTheme {
+    fn name(&self) -> &'static str {
+        "dungeon"
+    }
+
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
+        stone_glyph(tile_type)
+    }
+
+    fn tile_tint(&self, tile_type: TileType) -> RGB {
         match tile_type {
-            TileType::Floor => to_cp437('.'),
-            TileType::Wall => to_cp437('#'),
-            TileType::Exit => to_cp437('>'),
+            TileType::LockedDoor => GOLD.into(),
+            TileType::ShallowWater => LIGHT_BLUE.into(),
+            TileType::DeepWater => BLUE.into(),
+            TileType::Lava => ORANGE_RED.into(),
+            TileType::Rubble => GRAY.into(),
+            _ => WHITE.into(),
         }
     }
 
-    fn get_architect(&self) -> Box<dyn super::MapArchitect> {
-        Box::new(RoomsArchitect {})
+    fn tile_description(&self, tile_type: TileType) -> &'static str {
+        match tile_type {
+            TileType::Floor => "A worn flagstone floor.",
+            TileType::Wall => "A wall of rough-hewn stone blocks.",
+            TileType::Exit => "A stairway leading deeper into the dungeon.",
+            TileType::Door => "A heavy wooden door. It isn't locked.",
+            TileType::OpenDoor => "A wooden door, standing open.",
+            TileType::LockedDoor => "A heavy wooden door bound with iron. It needs a key.",
+            TileType::ShallowWater => "A puddle of cold, stagnant water. Wading through it is slow.",
+            TileType::DeepWater => "A flooded pit, too deep to wade.",
+            TileType::Lava => "A seam of molten rock. It burns anything that stands in it.",
+            TileType::Rubble => "A heap of fallen masonry. You can clamber over it, but not see past it.",
+        }
+    }
+
+    fn architect_weights(&self) -> &'static [(&'static str, i32)] {
+        &[
+            ("rooms", 3),
+            ("bsp", 3),
+            ("maze", 1),
+            ("voronoi", 2),
+            ("dla", 1),
+            ("symmetric", 1),
+        ]
     }
 }
 
 pub struct ForestTheme {}
 
 impl ForestTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
 }
 
 impl MapTheme for ForestTheme {
+    fn name(&self) -> &'static str {
+        "forest"
+    }
+
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
         match tile_type {
             TileType::Floor => to_cp437(';'),
             TileType::Wall => to_cp437('"'),
             TileType::Exit => to_cp437('>'),
+            TileType::Door => to_cp437('#'),
+            TileType::OpenDoor => to_cp437(';'),
+            TileType::LockedDoor => to_cp437('#'),
+            TileType::ShallowWater => to_cp437('~'),
+            TileType::DeepWater => to_cp437('='),
+            TileType::Lava => to_cp437('^'),
+            TileType::Rubble => to_cp437(':'),
+        }
+    }
+
+    fn tile_tint(&self, tile_type: TileType) -> RGB {
+        match tile_type {
+            TileType::Floor | TileType::OpenDoor => LIME_GREEN.into(),
+            TileType::Wall => FOREST_GREEN.into(),
+            TileType::LockedDoor => GOLD.into(),
+            TileType::ShallowWater => CYAN.into(),
+            TileType::DeepWater => NAVY.into(),
+            TileType::Lava => RED.into(),
+            TileType::Rubble => DARK_GREEN.into(),
+            _ => WHITE.into(),
+        }
+    }
+
+    fn remembered_tint(&self) -> RGB {
+        OLIVE.into()
+    }
+
+    fn tile_description(&self, tile_type: TileType) -> &'static str {
+        match tile_type {
+            TileType::Floor => "Soft, mossy ground littered with leaves.",
+            TileType::Wall => "A tangle of trees too dense to pass.",
+            TileType::Exit => "A hollow in the roots leading further down.",
+            TileType::Door => "A gate in an old stone wall, overgrown but unlocked.",
+            TileType::OpenDoor => "An open gateway through an old stone wall.",
+            TileType::LockedDoor => "A rusted iron gate, chained shut. It needs a key.",
+            TileType::ShallowWater => "A muddy stream. Wading through it is slow.",
+            TileType::DeepWater => "A dark forest pool, too deep to wade.",
+            TileType::Lava => "A smouldering fissure in the earth. It burns anything that stands in it.",
+            TileType::Rubble => "A thicket of brambles. You can push through, but not see past it.",
+        }
+    }
+
+    /// The trees close in, so there's less to see.
+    fn visibility_radius(&self) -> i32 {
+        6
+    }
+
+    fn architect_weights(&self) -> &'static [(&'static str, i32)] {
+        &[("drunkard", 2), ("automata", 3), ("dla", 1)]
+    }
+}
+
+pub struct CavernsTheme {}
+
+impl CavernsTheme {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> Box<dyn MapTheme> {
+        Box::new(Self {})
+    }
+}
+
+impl MapTheme for CavernsTheme {
+    fn name(&self) -> &'static str {
+        "caverns"
+    }
+
+    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
+        stone_glyph(tile_type)
+    }
+
+    fn tile_tint(&self, tile_type: TileType) -> RGB {
+        match tile_type {
+            TileType::Floor | TileType::OpenDoor => TAN.into(),
+            TileType::Wall => SIENNA.into(),
+            TileType::LockedDoor => GOLD.into(),
+            TileType::ShallowWater => TEAL.into(),
+            TileType::DeepWater => BLUE.into(),
+            TileType::Lava => ORANGE_RED.into(),
+            TileType::Rubble => PERU.into(),
+            _ => WHITE.into(),
+        }
+    }
+
+    fn remembered_tint(&self) -> RGB {
+        SADDLE_BROWN.into()
+    }
+
+    fn tile_description(&self, tile_type: TileType) -> &'static str {
+        match tile_type {
+            TileType::Floor => "Damp, uneven rock, slick underfoot.",
+            TileType::Wall => "The bare rock of the cave wall, glistening with seepage.",
+            TileType::Exit => "A crevice that winds further down into the dark.",
+            TileType::Door => "A crude door wedged into a gap in the rock.",
+            TileType::OpenDoor => "A crude door, propped open with a stone.",
+            TileType::LockedDoor => "A slab of rock on an iron chain, padlocked in place. It needs a key.",
+            TileType::ShallowWater => "A pool of water dripped from the ceiling. Wading through it is slow.",
+            TileType::DeepWater => "An underground lake, black and too deep to wade.",
+            TileType::Lava => "Magma welling up through a crack. It burns anything that stands in it.",
+            TileType::Rubble => "A rockfall. You can scramble over it, but not see past it.",
         }
     }
 
-    fn get_architect(&self) -> Box<dyn super::MapArchitect> {
-        let mut rng = RandomNumberGenerator::new();
-        match rng.range(0, 1) {
-            0 => Box::new(DrunkardsWalkArchitect {}),
-            _ => Box::new(CellularAutomataArchitect {})
+    fn visibility_radius(&self) -> i32 {
+        6
+    }
+
+    fn ambient_light(&self) -> f32 {
+        0.4
+    }
+
+    fn architect_weights(&self) -> &'static [(&'static str, i32)] {
+        &[("automata", 3), ("drunkard", 2), ("dla", 2), ("symmetric", 1)]
+    }
+}
+
+pub struct CryptTheme {}
+
+impl CryptTheme {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> Box<dyn MapTheme> {
+        Box::new(Self {})
+    }
+}
+
+impl MapTheme for CryptTheme {
+    fn name(&self) -> &'static str {
+        "crypt"
+    }
+
+    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
+        stone_glyph(tile_type)
+    }
+
+    fn tile_tint(&self, tile_type: TileType) -> RGB {
+        match tile_type {
+            TileType::Floor | TileType::OpenDoor => LIGHT_GRAY.into(),
+            TileType::Wall => SLATE_GRAY.into(),
+            TileType::Door => THISTLE.into(),
+            TileType::LockedDoor => GOLD.into(),
+            TileType::ShallowWater => STEEL_BLUE.into(),
+            TileType::DeepWater => MIDNIGHT_BLUE.into(),
+            TileType::Lava => ORANGE_RED.into(),
+            TileType::Rubble => DIM_GRAY.into(),
+            _ => WHITE.into(),
+        }
+    }
+
+    fn remembered_tint(&self) -> RGB {
+        DIM_GRAY.into()
+    }
+
+    fn tile_description(&self, tile_type: TileType) -> &'static str {
+        match tile_type {
+            TileType::Floor => "Flagstones carved with the names of the dead.",
+            TileType::Wall => "A wall of burial niches, each one sealed with a slab.",
+            TileType::Exit => "Narrow steps down to an older tomb below.",
+            TileType::Door => "A stone door on a pivot. It isn't sealed.",
+            TileType::OpenDoor => "A stone door, turned aside on its pivot.",
+            TileType::LockedDoor => "A tomb door sealed with a heavy lock. It needs a key.",
+            TileType::ShallowWater => "Water that has seeped into a sunken grave. Wading through it is slow.",
+            TileType::DeepWater => "A flooded burial pit, too deep to wade.",
+            TileType::Lava => "A brazier's coals, spilled across the floor. They burn anything that stands in them.",
+            TileType::Rubble => "A collapsed sarcophagus. You can climb over it, but not see past it.",
+        }
+    }
+
+    /// The crypt is pitch black beyond the reach of the player's torch.
+    fn visibility_radius(&self) -> i32 {
+        5
+    }
+
+    fn ambient_light(&self) -> f32 {
+        0.25
+    }
+
+    fn architect_weights(&self) -> &'static [(&'static str, i32)] {
+        &[("bsp", 3), ("rooms", 2), ("maze", 2)]
+    }
+}
+
+/// How many extra pools the flooded ruins start with, on top of the usual
+/// terrain.
+const FLOODED_POOLS: usize = 8;
+
+pub struct FloodedRuinsTheme {}
+
+impl FloodedRuinsTheme {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> Box<dyn MapTheme> {
+        Box::new(Self {})
+    }
+}
+
+impl MapTheme for FloodedRuinsTheme {
+    fn name(&self) -> &'static str {
+        "flooded_ruins"
+    }
+
+    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
+        stone_glyph(tile_type)
+    }
+
+    fn tile_tint(&self, tile_type: TileType) -> RGB {
+        match tile_type {
+            TileType::Floor | TileType::OpenDoor => WHEAT.into(),
+            TileType::Wall => CADET_BLUE.into(),
+            TileType::LockedDoor => GOLD.into(),
+            TileType::ShallowWater => LIGHT_BLUE.into(),
+            TileType::DeepWater => BLUE.into(),
+            TileType::Lava => ORANGE_RED.into(),
+            TileType::Rubble => SEA_GREEN.into(),
+            _ => WHITE.into(),
+        }
+    }
+
+    fn remembered_tint(&self) -> RGB {
+        STEEL_BLUE.into()
+    }
+
+    fn tile_description(&self, tile_type: TileType) -> &'static str {
+        match tile_type {
+            TileType::Floor => "Mosaic tiles under a film of silt.",
+            TileType::Wall => "A weed-hung wall, crusted with the marks of old floods.",
+            TileType::Exit => "A stairwell half full of water, leading further down.",
+            TileType::Door => "A swollen wooden door. It isn't locked.",
+            TileType::OpenDoor => "A swollen wooden door, jammed open.",
+            TileType::LockedDoor => "A bronze door green with age, still locked. It needs a key.",
+            TileType::ShallowWater => "Knee-deep floodwater. Wading through it is slow.",
+            TileType::DeepWater => "A flooded hall, too deep to wade. Something moves beneath the surface.",
+            TileType::Lava => "A vent of scalding steam. It burns anything that stands in it.",
+            TileType::Rubble => "A fallen column, furred with weed. You can clamber over it, but not see past it.",
+        }
+    }
+
+    fn ambient_light(&self) -> f32 {
+        0.5
+    }
+
+    fn architect_weights(&self) -> &'static [(&'static str, i32)] {
+        &[("voronoi", 3), ("rooms", 2), ("bsp", 1)]
+    }
+
+    fn get_architect(&self, rng: &mut RandomNumberGenerator) -> Box<dyn MapArchitect> {
+        let initial = choose_architect(self.architect_weights(), rng);
+        Box::new(MapPipeline::new(initial).with(Flood {
+            pools: FLOODED_POOLS,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_choose_every_architect_a_theme_weights() {
+        let theme = ForestTheme::new();
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mut sizes: Vec<(i32, i32)> = (0..50)
+            .map(|_| {
+                let mb = theme.get_architect(&mut rng).create_map_builder(&mut rng);
+                (mb.map.width, mb.map.height)
+            })
+            .collect();
+        sizes.sort();
+        sizes.dedup();
+
+        assert_eq!(sizes, vec![(80, 50), (100, 70), (120, 80)]);
+    }
+
+    #[test]
+    fn should_give_every_terrain_tile_its_own_glyph() {
+        let tiles = [
+            TileType::Floor,
+            TileType::Wall,
+            TileType::Door,
+            TileType::ShallowWater,
+            TileType::DeepWater,
+            TileType::Lava,
+            TileType::Rubble,
+        ];
+        for name in THEME_NAMES {
+            let theme = theme_named(name).unwrap();
+            let mut glyphs: Vec<FontCharType> =
+                tiles.iter().map(|t| theme.tile_to_render(*t)).collect();
+            glyphs.sort();
+            glyphs.dedup();
+
+            assert_eq!(glyphs.len(), tiles.len(), "{}", name);
         }
     }
 }
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+use crate::{prelude::*, spawner::template::EntityType};
+
+/// Fewest tiles that must be reachable from the start for a level to be kept.
+const MIN_FLOOR_TILES: usize = 300;
+/// Fewest places a level must offer for monsters and items.
+const MIN_SPAWN_LOCATIONS: usize = 10;
+
+/// Why a generated level was thrown away.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapProblem {
+    Unreachable(Point),
+    TooLittleFloor(usize),
+    TooFewSpawns(usize),
+}
+
+impl MapBuilder {
+    /// Tunnels through to any part of the level that can't be reached from
+    /// the player's start and drops spawn locations that still can't be, then
+    /// checks the level is big and busy enough to be worth playing.
+    pub fn validate(&mut self) -> Result<(), MapProblem> {
+        if let Some(idx) = self.map.try_idx(self.player_start) {
+            if !is_passable(self.map.tiles[idx]) {
+                self.map.tiles[idx] = TileType::Floor;
+            }
+        }
+        self.connect_regions();
+
+        let reachable = self.reachable();
+        let map = &self.map;
+        let is_reachable = |pt: Point| map.try_idx(pt).is_some_and(|idx| reachable[idx]);
+        if !is_reachable(self.amulet_start) {
+            return Err(MapProblem::Unreachable(self.amulet_start));
+        }
+        if let Some(key) = self.key_start.filter(|key| !is_reachable(*key)) {
+            return Err(MapProblem::Unreachable(key));
+        }
+        self.spawn_locations
+            .retain(|spawn| is_reachable(spawn.point));
+
+        let floor = reachable.iter().filter(|r| **r).count();
+        if floor < MIN_FLOOR_TILES {
+            return Err(MapProblem::TooLittleFloor(floor));
+        }
+        let spawns = self
+            .spawn_locations
+            .iter()
+            .filter(|spawn| {
+                !matches!(
+                    spawn.preferred_entity,
+                    Some(EntityType::Trap | EntityType::Prop)
+                )
+            })
+            .count();
+        if spawns < MIN_SPAWN_LOCATIONS {
+            return Err(MapProblem::TooFewSpawns(spawns));
+        }
+        Ok(())
+    }
+
+    /// Digs a tunnel from the nearest reachable tile to each stranded region
+    /// in turn until everything walkable can be walked to.
+    fn connect_regions(&mut self) {
+        loop {
+            let reachable = self.reachable();
+            let stranded = match self
+                .map
+                .tiles
+                .iter()
+                .enumerate()
+                .find(|(idx, t)| !reachable[*idx] && is_passable(**t))
+            {
+                Some((idx, _)) => self.map.index_to_point2d(idx),
+                None => return,
+            };
+            let nearest = reachable
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| **r)
+                .map(|(idx, _)| self.map.index_to_point2d(idx))
+                .min_by(|a, b| {
+                    let distance = |pt| DistanceAlg::Pythagoras.distance2d(stranded, pt);
+                    distance(*a).partial_cmp(&distance(*b)).unwrap()
+                })
+                .unwrap_or(self.player_start);
+            self.dig(nearest, stranded);
+            self.take_snapshot();
+        }
+    }
+
+    fn dig(&mut self, from: Point, to: Point) {
+        use std::cmp::{max, min};
+        let horizontal = (min(from.x, to.x)..=max(from.x, to.x)).map(|x| Point::new(x, from.y));
+        let vertical = (min(from.y, to.y)..=max(from.y, to.y)).map(|y| Point::new(to.x, y));
+        for pt in horizontal.chain(vertical) {
+            let idx = self.map.map_idx(pt.x, pt.y);
+            if !is_passable(self.map.tiles[idx]) {
+                self.map.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+
+    /// How many steps it takes to walk from the player's start to the amulet,
+    /// or to the stairs down.
+    pub fn path_length(&self) -> Option<usize> {
+        self.map
+            .try_idx(self.amulet_start)
+            .and_then(|idx| self.distances()[idx])
+    }
+
+    /// Which tiles can be walked to from the player's start. Locked doors
+    /// count as open, since their key is always left on the near side.
+    pub(super) fn reachable(&self) -> Vec<bool> {
+        self.distances().iter().map(|d| d.is_some()).collect()
+    }
+
+    /// The number of steps from the player's start to each tile, if it can be
+    /// reached at all.
+    fn distances(&self) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.map.tiles.len()];
+        let start = match self.map.try_idx(self.player_start) {
+            Some(idx) if is_passable(self.map.tiles[idx]) => idx,
+            _ => return distances,
+        };
+        distances[start] = Some(0);
+        let mut frontier = VecDeque::from([self.player_start]);
+        while let Some(pt) = frontier.pop_front() {
+            let distance = distances[self.map.point2d_to_index(pt)].unwrap_or(0) + 1;
+            for delta in [
+                Point::new(-1, 0),
+                Point::new(1, 0),
+                Point::new(0, -1),
+                Point::new(0, 1),
+            ] {
+                let next = pt + delta;
+                if let Some(idx) = self.map.try_idx(next) {
+                    if distances[idx].is_none() && is_passable(self.map.tiles[idx]) {
+                        distances[idx] = Some(distance);
+                        frontier.push_back(next);
+                    }
+                }
+            }
+        }
+        distances
+    }
+}
+
+fn is_passable(tile: TileType) -> bool {
+    tile == TileType::LockedDoor || tile.movement_cost().is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn walled_level(rooms: &[Rect]) -> MapBuilder {
+        let mut mb = MapBuilder::with_map(
+            Map::new(60, 30),
+            rooms[0].center(),
+            rooms[rooms.len() - 1].center(),
+        );
+        mb.rooms = rooms.to_vec();
+        mb.fill(TileType::Wall);
+        rooms.iter().for_each(|room| {
+            room.for_each(|pt| {
+                let idx = mb.map.map_idx(pt.x, pt.y);
+                mb.map.tiles[idx] = TileType::Floor;
+            })
+        });
+        mb
+    }
+
+    #[test]
+    fn should_tunnel_through_to_stranded_caves() {
+        let mut mb = walled_level(&[Rect::with_size(2, 2, 5, 5), Rect::with_size(40, 20, 6, 6)]);
+
+        mb.connect_regions();
+
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+        assert!(dijkstra_map.map[mb.map.point2d_to_index(mb.amulet_start)] < f32::MAX);
+    }
+
+    #[test]
+    fn should_reject_a_level_with_too_little_floor() {
+        let mut mb = walled_level(&[Rect::with_size(2, 2, 5, 5), Rect::with_size(10, 2, 5, 5)]);
+
+        let result = mb.validate();
+
+        assert!(matches!(result, Err(MapProblem::TooLittleFloor(_))));
+    }
+
+    #[test]
+    fn should_drop_spawns_that_cannot_be_reached() {
+        let mut mb = walled_level(&[Rect::with_size(1, 1, 58, 28)]);
+        mb.amulet_start = Point::new(50, 20);
+        let wall = mb.map.map_idx(30, 10);
+        mb.map.tiles[wall] = TileType::Wall;
+        for x in 20..=30 {
+            mb.spawn_locations.push(SpawnLocation {
+                point: Point::new(x, 10),
+                preferred_entity: None,
+                template: None,
+            });
+        }
+
+        let result = mb.validate();
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(mb.spawn_locations.len(), 10);
+        assert!(mb.spawn_locations.iter().all(|spawn| spawn.point.x < 30));
+    }
+
+    #[test]
+    fn should_count_the_steps_to_the_amulet() {
+        let mut mb = walled_level(&[Rect::with_size(2, 2, 5, 5), Rect::with_size(10, 2, 5, 5)]);
+        mb.player_start = Point::new(2, 2);
+        mb.amulet_start = Point::new(14, 2);
+
+        assert_eq!(mb.path_length(), None);
+
+        mb.connect_regions();
+
+        assert_eq!(mb.path_length(), Some(12));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::prelude::*;
+
+/// How many floor tiles the vault extends from the amulet in each direction.
+const VAULT_RADIUS: i32 = 3;
+
+impl MapBuilder {
+    /// Carves a walled chamber around the amulet for its guardian to hold.
+    /// Wherever the path from the player's start crosses the vault wall, the
+    /// wall is left open so the chamber can still be reached.
+    pub fn build_vault(&mut self) {
+        let center = self.amulet_start;
+        let x1 = (center.x - VAULT_RADIUS - 1).max(0);
+        let y1 = (center.y - VAULT_RADIUS - 1).max(0);
+        let x2 = (center.x + VAULT_RADIUS + 1).min(self.map.width - 1);
+        let y2 = (center.y + VAULT_RADIUS + 1).min(self.map.height - 1);
+        let inside = |pt: Point| pt.x >= x1 && pt.x <= x2 && pt.y >= y1 && pt.y <= y2;
+        if inside(self.player_start) {
+            return;
+        }
+
+        let entrance = self.path_to_amulet();
+        for y in y1..=y2 {
+            for x in x1..=x2 {
+                let on_edge = x == x1 || x == x2 || y == y1 || y == y2;
+                let at_map_edge =
+                    x == 0 || y == 0 || x == self.map.width - 1 || y == self.map.height - 1;
+                let tile = if at_map_edge || (on_edge && !entrance.contains(&Point::new(x, y))) {
+                    TileType::Wall
+                } else {
+                    TileType::Floor
+                };
+                let idx = self.map.map_idx(x, y);
+                self.map.tiles[idx] = tile;
+            }
+        }
+        self.spawn_locations.retain(|pt| !inside(pt.point));
+        self.take_snapshot();
+    }
+
+    fn path_to_amulet(&self) -> Vec<Point> {
+        let dijkstra_map = DijkstraMap::new(
+            self.map.width,
+            self.map.height,
+            &[self.map.point2d_to_index(self.player_start)],
+            &self.map,
+            1024.0,
+        );
+
+        let mut path = Vec::new();
+        let mut idx = self.map.point2d_to_index(self.amulet_start);
+        while let Some(next) = DijkstraMap::find_lowest_exit(&dijkstra_map, idx, &self.map)
+            .filter(|next| dijkstra_map.map[*next] < dijkstra_map.map[idx])
+        {
+            path.push(self.map.index_to_point2d(idx));
+            idx = next;
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_amulet_reachable_through_the_vault_wall() {
+        let mut map = Map::new(50, 20);
+        map.tiles.iter_mut().for_each(|t| *t = TileType::Wall);
+        for x in 1..map.width - 1 {
+            let idx = map.map_idx(x, 10);
+            map.tiles[idx] = TileType::Floor;
+        }
+        let mut mb = MapBuilder::with_map(map, Point::new(1, 10), Point::new(40, 10));
+        mb.spawn_locations.push(SpawnLocation {
+            point: Point::new(40, 10),
+            preferred_entity: None,
+            template: None,
+        });
+
+        mb.build_vault();
+
+        assert_eq!(mb.map.tiles[mb.map.map_idx(36, 9)], TileType::Wall);
+        assert_eq!(mb.map.tiles[mb.map.map_idx(38, 12)], TileType::Floor);
+        assert!(mb.spawn_locations.is_empty());
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+        assert!(dijkstra_map.map[mb.map.point2d_to_index(mb.amulet_start)] < f32::MAX);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use super::{themes::DungeonTheme, MapArchitect};
+use crate::prelude::*;
+
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 50;
+const NUM_SEEDS: usize = 32;
+
+/// Divides the map into the cells of a Voronoi diagram, walls them off from
+/// one another, and then knocks a gap through every shared wall so that the
+/// hive of cells can be explored.
+pub struct VoronoiArchitect {}
+
+impl MapArchitect for VoronoiArchitect {
+    fn create_map_builder(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(WIDTH, HEIGHT),
+            rooms: Vec::new(),
+            spawn_locations: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            key_start: None,
+            history: Vec::new(),
+            theme: DungeonTheme::new(),
+        };
+
+        let seeds = self.place_seeds(&mb.map, rng);
+        let owners = self.assign_cells(&mb.map, &seeds);
+        self.build_walls(&mut mb.map, &owners);
+        mb.take_snapshot();
+        self.open_gaps(&mut mb.map, &owners, rng);
+        mb.take_snapshot();
+
+        let start = self.nearest_floor(&mb.map, seeds[0]);
+        mb.spawn_locations = mb.get_spawn_plan(&start, rng);
+        mb.player_start = start;
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+}
+
+impl VoronoiArchitect {
+    fn place_seeds(&self, map: &Map, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let mut seeds: Vec<Point> = Vec::new();
+        while seeds.len() < NUM_SEEDS {
+            let seed = Point::new(rng.range(1, map.width - 1), rng.range(1, map.height - 1));
+            if !seeds.contains(&seed) {
+                seeds.push(seed);
+            }
+        }
+        seeds
+    }
+
+    /// The index of the closest seed to each tile.
+    fn assign_cells(&self, map: &Map, seeds: &[Point]) -> Vec<usize> {
+        (0..map.tiles.len())
+            .map(|idx| {
+                let pt = map.index_to_point2d(idx);
+                seeds
+                    .iter()
+                    .enumerate()
+                    .map(|(seed, pos)| (seed, DistanceAlg::PythagorasSquared.distance2d(pt, *pos)))
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                    .map(|(seed, _)| seed)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Walls the edge of the map and every tile whose right or lower
+    /// neighbour belongs to another cell, giving walls one tile thick.
+    fn build_walls(&self, map: &mut Map, owners: &[usize]) {
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let idx = map.map_idx(x, y);
+                let on_edge = x == 0 || y == 0 || x == map.width - 1 || y == map.height - 1;
+                let on_boundary = !on_edge
+                    && (owners[idx] != owners[map.map_idx(x + 1, y)]
+                        || owners[idx] != owners[map.map_idx(x, y + 1)]);
+                map.tiles[idx] = if on_edge || on_boundary {
+                    TileType::Wall
+                } else {
+                    TileType::Floor
+                };
+            }
+        }
+    }
+
+    /// Opens one randomly chosen wall tile between each pair of neighbouring
+    /// cells.
+    fn open_gaps(&self, map: &mut Map, owners: &[usize], rng: &mut RandomNumberGenerator) {
+        let mut gaps: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
+                let idx = map.map_idx(x, y);
+                if map.tiles[idx] != TileType::Wall {
+                    continue;
+                }
+                let across = [
+                    (map.map_idx(x - 1, y), map.map_idx(x + 1, y)),
+                    (map.map_idx(x, y - 1), map.map_idx(x, y + 1)),
+                ];
+                for (a, b) in across {
+                    let both_floor =
+                        map.tiles[a] == TileType::Floor && map.tiles[b] == TileType::Floor;
+                    if both_floor && owners[a] != owners[b] {
+                        let pair = (owners[a].min(owners[b]), owners[a].max(owners[b]));
+                        gaps.entry(pair).or_default().push(idx);
+                    }
+                }
+            }
+        }
+        gaps.values().for_each(|candidates| {
+            if let Some(idx) = rng.random_slice_entry(candidates) {
+                map.tiles[*idx] = TileType::Floor;
+            }
+        });
+    }
+
+    fn nearest_floor(&self, map: &Map, target: Point) -> Point {
+        map.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| map.index_to_point2d(idx))
+            .min_by(|a, b| {
+                let distance = |pt| DistanceAlg::Pythagoras.distance2d(target, pt);
+                distance(*a).partial_cmp(&distance(*b)).unwrap()
+            })
+            .unwrap_or(target)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_link_every_cell_of_the_hive() {
+        let mut rng = RandomNumberGenerator::seeded(4);
+        let mb = VoronoiArchitect {}.create_map_builder(&mut rng);
+
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+        let floor = mb
+            .map
+            .tiles
+            .iter()
+            .filter(|t| **t == TileType::Floor)
+            .count();
+        let reached = dijkstra_map.map.iter().filter(|d| **d < f32::MAX).count();
+
+        assert!(floor > (WIDTH * HEIGHT) as usize / 2);
+        assert_eq!(reached, floor);
+    }
+}
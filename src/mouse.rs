@@ -0,0 +1,2 @@
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MouseClick(pub bool);
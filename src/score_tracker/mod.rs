@@ -5,6 +5,12 @@ pub struct ScoreTracker {
     start_time: SystemTime
 }
 
+impl Default for ScoreTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ScoreTracker {
     pub fn new() -> Self {
         Self {
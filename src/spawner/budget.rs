@@ -0,0 +1,120 @@
+use serde::Deserialize;
+
+/// Floor area that the configured budgets are balanced for. Bigger or
+/// smaller levels get proportionally more or less to spend.
+const REFERENCE_FLOOR_TILES: f32 = 1000.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    fn threat_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        }
+    }
+
+    fn item_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.25,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.75,
+        }
+    }
+}
+
+/// How much threat and how many items the first level gets, and how that
+/// changes with every level deeper into the dungeon.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct SpawnBudgetConfig {
+    pub threat: i32,
+    pub threat_per_level: i32,
+    pub items: i32,
+    pub items_per_level: i32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpawnBudget {
+    pub threat: i32,
+    pub items: i32,
+}
+
+impl SpawnBudgetConfig {
+    pub fn for_level(
+        &self,
+        level: usize,
+        difficulty: Difficulty,
+        floor_tiles: usize,
+    ) -> SpawnBudget {
+        let level = level as i32;
+        let area = (floor_tiles as f32 / REFERENCE_FLOOR_TILES).clamp(0.5, 2.0);
+        let threat = (self.threat + self.threat_per_level * level).max(0) as f32;
+        let items = (self.items + self.items_per_level * level).max(0) as f32;
+        SpawnBudget {
+            threat: (threat * area * difficulty.threat_multiplier()).round() as i32,
+            items: (items * area * difficulty.item_multiplier()).round() as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    const CONFIG: SpawnBudgetConfig = SpawnBudgetConfig {
+        threat: 20,
+        threat_per_level: 10,
+        items: 8,
+        items_per_level: -1,
+    };
+
+    #[test]
+    fn should_scale_with_depth() {
+        let first = CONFIG.for_level(0, Difficulty::Normal, 1000);
+        let third = CONFIG.for_level(2, Difficulty::Normal, 1000);
+        assert_eq!(
+            first,
+            SpawnBudget {
+                threat: 20,
+                items: 8
+            }
+        );
+        assert_eq!(
+            third,
+            SpawnBudget {
+                threat: 40,
+                items: 6
+            }
+        );
+    }
+
+    #[test]
+    fn should_scale_with_difficulty() {
+        let easy = CONFIG.for_level(0, Difficulty::Easy, 1000);
+        let hard = CONFIG.for_level(0, Difficulty::Hard, 1000);
+        assert!(easy.threat < hard.threat);
+        assert!(easy.items > hard.items);
+    }
+
+    #[test]
+    fn should_scale_with_floor_area() {
+        let small = CONFIG.for_level(0, Difficulty::Normal, 500);
+        let large = CONFIG.for_level(0, Difficulty::Normal, 2000);
+        assert_eq!(small.threat, 10);
+        assert_eq!(large.threat, 40);
+    }
+}
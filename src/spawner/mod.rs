@@ -1,27 +1,55 @@
+pub mod budget;
 pub mod template;
 
 use crate::{
     components::{Player, Render},
-    prelude::{AmuletOfYala, Damage, FieldOfView, Health, Item, Name, SpawnLocation},
+    prelude::{
+        AmuletOfYala, Damage, Description, FieldOfView, Health, Item, Key, LightSource, LootEntry,
+        Map, Name, SpawnLocation,
+    },
 };
 use bracket_lib::{
     random::RandomNumberGenerator,
-    terminal::{to_cp437, ColorPair, Point, BLACK, WHITE},
+    terminal::{to_cp437, ColorPair, Point, BLACK, GOLD, WHEAT, WHITE},
 };
 use legion::World;
 
+use self::budget::Difficulty;
 use self::template::{FileEntityLoader, Spawner, TemplateSpawner};
 
+pub fn load_templates() -> TemplateSpawner<Spawner> {
+    let spawner = Spawner::default();
+    let file_reader = FileEntityLoader::new(String::from("resources/template.ron"));
+    TemplateSpawner::new(&file_reader, spawner)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_level(
     ecs: &mut World,
+    templates: &TemplateSpawner<Spawner>,
     rng: &mut RandomNumberGenerator,
     level: usize,
+    theme: &str,
+    difficulty: Difficulty,
     spawn_points: &[SpawnLocation],
+    map: &Map,
 ) {
-    let spawner = Spawner::default();
-    let file_reader = FileEntityLoader::new(String::from("resources/template.ron"));
-    let template = TemplateSpawner::new(&file_reader, spawner);
-    template.spawn_entities(ecs, rng, level, spawn_points);
+    templates.spawn_entities(ecs, rng, level, theme, difficulty, spawn_points, map);
+}
+
+/// Rolls each entry of a loot table, returning the names of the item
+/// templates that should be dropped.
+pub fn roll_loot<'a>(loot: &'a [LootEntry], rng: &mut RandomNumberGenerator) -> Vec<&'a str> {
+    let mut drops = Vec::new();
+    for entry in loot.iter() {
+        if rng.range(0, 100) < entry.chance {
+            let (min, max) = entry.quantity;
+            for _ in 0..rng.range(min, max + 1) {
+                drops.push(entry.item.as_str());
+            }
+        }
+    }
+    drops
 }
 
 pub fn spawn_player(ecs: &mut World, pos: Point) {
@@ -38,6 +66,10 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
         },
         FieldOfView::new(8),
         Damage(1),
+        LightSource {
+            color: WHEAT,
+            range: 6,
+        },
     ));
 }
 
@@ -51,5 +83,59 @@ pub fn spawn_amulet_of_yala(ecs: &mut World, position: Point) {
             glyph: to_cp437('|'),
         },
         Name("Amulet of Yala".to_string()),
+        Description(
+            "Yet Another Lost Amulet. Legend says it can stem the tide of monsters.".to_string(),
+        ),
     ));
 }
+
+pub fn spawn_key(ecs: &mut World, position: Point) {
+    ecs.push((
+        Item,
+        Key,
+        position,
+        Render {
+            color: ColorPair::new(GOLD, BLACK),
+            glyph: to_cp437('k'),
+        },
+        Name("Iron Key".to_string()),
+        Description("A heavy iron key. It should open a locked door on this level.".to_string()),
+    ));
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    fn entry(item: &str, chance: i32, quantity: (i32, i32)) -> LootEntry {
+        LootEntry {
+            item: String::from(item),
+            chance,
+            quantity,
+        }
+    }
+
+    #[test]
+    fn should_always_drop_certain_loot() {
+        let mut rng = RandomNumberGenerator::new();
+        let loot = vec![entry("Healing Potion", 100, (2, 2))];
+        assert_eq!(roll_loot(&loot, &mut rng), vec!["Healing Potion"; 2]);
+    }
+
+    #[test]
+    fn should_never_drop_impossible_loot() {
+        let mut rng = RandomNumberGenerator::new();
+        let loot = vec![entry("Huge Sword", 0, (1, 1))];
+        assert!(roll_loot(&loot, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn should_drop_a_quantity_within_range() {
+        let mut rng = RandomNumberGenerator::new();
+        let loot = vec![entry("Healing Potion", 100, (1, 3))];
+        for _ in 0..20 {
+            let drops = roll_loot(&loot, &mut rng).len();
+            assert!((1..=3).contains(&drops));
+        }
+    }
+}
@@ -3,10 +3,11 @@ use legion::systems::CommandBuffer;
 use legion::World;
 use mockall::*;
 use ron::de::from_reader;
-use serde::Deserialize;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
 use std::fs::File;
 
+use super::budget::{Difficulty, SpawnBudget, SpawnBudgetConfig};
 use crate::prelude::*;
 
 #[derive(Clone, Deserialize, Debug)]
@@ -19,11 +20,45 @@ pub struct Template {
     pub provides: Option<Vec<(String, i32)>>,
     pub hp: Option<i32>,
     pub base_damage: Option<i32>,
+    pub description: Option<String>,
+    pub behaviour: Option<Behaviour>,
+    pub flee_below: Option<i32>,
+    pub carries: Option<Vec<String>>,
+    pub loot: Option<Vec<LootEntry>>,
+    pub group: Option<GroupSpawn>,
+    pub threat: Option<i32>,
+    pub boss: Option<bool>,
+    pub abilities: Option<Vec<Ability>>,
+    pub trap: Option<TrapEffect>,
+    /// The themes whose levels this appears on. Templates that don't say
+    /// appear on any level.
+    pub themes: Option<HashSet<String>>,
+    /// The colour it's drawn in, if not the default for its kind.
+    pub color: Option<(u8, u8, u8)>,
+    pub light: Option<LightSource>,
+}
+
+impl Template {
+    /// How much of a level's threat budget one of these monsters uses up.
+    pub fn threat(&self) -> i32 {
+        self.threat.unwrap_or(1)
+    }
+}
+
+/// Spawns a template as a pack rather than a lone monster. The pack has
+/// between `min` and `max` members in total, one of which is the `leader`
+/// template if one is given.
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub struct GroupSpawn {
+    pub min: i32,
+    pub max: i32,
+    pub leader: Option<String>,
 }
 
 #[automock]
 pub trait CanLoadEntities {
     fn load_entities(&self) -> Vec<Template>;
+    fn load_budget(&self) -> SpawnBudgetConfig;
 }
 
 pub struct FileEntityLoader {
@@ -36,18 +71,27 @@ impl FileEntityLoader {
     }
 }
 
+impl FileEntityLoader {
+    fn load_collection(&self) -> TemplateCollection {
+        let file = File::open(&self.file_path).expect("Failed to open file");
+        from_reader(file).expect("Unable to load templates")
+    }
+}
+
 impl CanLoadEntities for FileEntityLoader {
     fn load_entities(&self) -> Vec<Template> {
-        let file = File::open(&self.file_path).expect("Failed to open file");
-        let template_collection: TemplateCollection =
-            from_reader(file).expect("Unable to load templates");
-        template_collection.entities
+        self.load_collection().entities
+    }
+
+    fn load_budget(&self) -> SpawnBudgetConfig {
+        self.load_collection().budget
     }
 }
 
 #[derive(Clone, Deserialize, Debug)]
 struct TemplateCollection {
     pub entities: Vec<Template>,
+    pub budget: SpawnBudgetConfig,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -56,20 +100,25 @@ where
     T: CanSpawnEntities,
 {
     pub entities: Vec<Template>,
+    pub budget: SpawnBudgetConfig,
     #[serde(skip)]
     pub spawner: T,
 }
 
-#[derive(Copy, Clone, Deserialize, Debug, PartialEq)]
+#[derive(Copy, Clone, Deserialize, Serialize, Debug, PartialEq)]
 pub enum EntityType {
     Enemy,
     Item,
+    Trap,
+    /// Scenery such as braziers, which can't be picked up or fought.
+    Prop,
 }
 
 impl<T: CanSpawnEntities + Default> Default for TemplateSpawner<T> {
     fn default() -> Self {
         Self {
             entities: Default::default(),
+            budget: Default::default(),
             spawner: Default::default(),
         }
     }
@@ -80,33 +129,81 @@ pub struct Spawner {}
 
 #[automock]
 pub trait CanSpawnEntities {
-    fn spawn_entity(&self, pt: &SpawnLocation, template: &Template, commands: &mut CommandBuffer);
+    fn spawn_entity(
+        &self,
+        pt: &SpawnLocation,
+        template: &Template,
+        commands: &mut CommandBuffer,
+    ) -> Entity;
 }
 
+#[allow(dead_code)]
 #[automock]
 pub trait CanReadEntitiesFromFile {
     fn read_entities<T: CanSpawnEntities + Default + 'static>(&self) -> TemplateSpawner<T>;
 }
 
+#[allow(dead_code)]
 #[derive(Default)]
 pub struct FileReader {}
 
 impl CanSpawnEntities for Spawner {
-    fn spawn_entity(&self, pt: &SpawnLocation, template: &Template, commands: &mut CommandBuffer) {
+    fn spawn_entity(
+        &self,
+        pt: &SpawnLocation,
+        template: &Template,
+        commands: &mut CommandBuffer,
+    ) -> Entity {
+        let color = match (template.color, template.entity_type) {
+            (Some(color), _) => color,
+            (None, EntityType::Trap) => RED,
+            (None, _) => WHITE,
+        };
         let entity = commands.push((
             pt.point,
             Render {
-                color: ColorPair::new(WHITE, BLACK),
+                color: ColorPair::new(color, BLACK),
                 glyph: to_cp437(template.glyph),
             },
             Name(template.name.clone()),
         ));
+        if let Some(description) = &template.description {
+            commands.add_component(entity, Description(description.clone()));
+        }
+        if let Some(light) = template.light {
+            commands.add_component(entity, light);
+        }
         match template.entity_type {
             EntityType::Item => commands.add_component(entity, Item {}),
+            EntityType::Prop => {}
+            EntityType::Trap => {
+                commands.add_component(entity, Hidden);
+                if let Some(effect) = template.trap {
+                    commands.add_component(entity, Trap { effect });
+                }
+            }
             EntityType::Enemy => {
                 commands.add_component(entity, Enemy {});
                 commands.add_component(entity, FieldOfView::new(6));
-                commands.add_component(entity, ChasingPlayer {});
+                if let Some(loot) = &template.loot {
+                    commands.add_component(entity, LootTable(loot.clone()));
+                }
+                commands.add_component(
+                    entity,
+                    Brain::new(
+                        template.behaviour.unwrap_or(Behaviour::Guard),
+                        pt.point,
+                        template.flee_below.unwrap_or(0),
+                    ),
+                );
+                if template.boss == Some(true) {
+                    commands.add_component(entity, Boss { enraged: false });
+                }
+                if let Some(abilities) = &template.abilities {
+                    abilities
+                        .iter()
+                        .for_each(|ability| add_ability(entity, ability, commands));
+                }
                 commands.add_component(
                     entity,
                     Health {
@@ -135,6 +232,8 @@ impl CanSpawnEntities for Spawner {
                 commands.add_component(entity, Weapon {})
             }
         }
+
+        entity
     }
 }
 
@@ -144,50 +243,331 @@ where
 {
     pub fn new(loader: &dyn CanLoadEntities, spawner: T) -> Self {
         let entities = loader.load_entities();
-        TemplateSpawner { entities, spawner }
+        let budget = loader.load_budget();
+        TemplateSpawner {
+            entities,
+            budget,
+            spawner,
+        }
     }
 
+    /// Spawns a level's monsters and items. Locations asking for a specific
+    /// kind of entity are always filled; the rest are handed out until the
+    /// level's item budget and then its threat budget are spent. Only
+    /// templates that belong in `theme` are drawn at random.
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn_entities(
         &self,
         ecs: &mut World,
         rng: &mut RandomNumberGenerator,
         level: usize,
+        theme: &str,
+        difficulty: Difficulty,
         spawn_locations: &[SpawnLocation],
+        map: &Map,
     ) {
-        let mut available_entities = Vec::new();
+        let floor_tiles = map.tiles.iter().filter(|t| **t == TileType::Floor).count();
+        let mut budget = self.budget.for_level(level, difficulty, floor_tiles);
+        let enemies = self.weighted_pool(level, theme, EntityType::Enemy);
+        let items = self.weighted_pool(level, theme, EntityType::Item);
+        let traps = self.weighted_pool(level, theme, EntityType::Trap);
+        let props = self.weighted_pool(level, theme, EntityType::Prop);
+
+        let mut taken: HashSet<Point> = spawn_locations.iter().map(|pt| pt.point).collect();
+        let mut commands = CommandBuffer::new(ecs);
+
+        let (preferred, open): (Vec<&SpawnLocation>, Vec<&SpawnLocation>) = spawn_locations
+            .iter()
+            .partition(|pt| pt.preferred_entity.is_some() || pt.template.is_some());
+
+        preferred.into_iter().for_each(|pt| {
+            let pool = match pt.preferred_entity {
+                Some(EntityType::Enemy) => &enemies,
+                Some(EntityType::Trap) => &traps,
+                Some(EntityType::Prop) => &props,
+                _ => &items,
+            };
+            let template = match &pt.template {
+                Some(name) => self.entities.iter().find(|t| t.name == *name),
+                None => rng.random_slice_entry(pool).copied(),
+            };
+            if let Some(template) = template {
+                self.spawn_from_budget(
+                    pt,
+                    template,
+                    i32::MAX,
+                    &mut budget,
+                    map,
+                    &mut taken,
+                    rng,
+                    &mut commands,
+                );
+            }
+        });
+
+        let mut open = open.into_iter();
+        while budget.items > 0 {
+            let (pt, template) = match (open.next(), rng.random_slice_entry(&items)) {
+                (Some(pt), Some(template)) => (pt, *template),
+                _ => break,
+            };
+            self.spawn(pt, template, &mut commands);
+            budget.items -= 1;
+        }
+
+        while budget.threat > 0 {
+            let affordable: Vec<&Template> = enemies
+                .iter()
+                .filter(|template| self.leader_of(template).threat() <= budget.threat)
+                .copied()
+                .collect();
+            let (pt, template) = match (open.next(), rng.random_slice_entry(&affordable)) {
+                (Some(pt), Some(template)) => (pt, *template),
+                _ => break,
+            };
+            self.spawn_from_budget(
+                pt,
+                template,
+                budget.threat,
+                &mut budget,
+                map,
+                &mut taken,
+                rng,
+                &mut commands,
+            );
+        }
+        commands.flush(ecs);
+    }
+
+    /// Spawns the boss meant for `level` at `point`, if the templates define one.
+    pub fn spawn_boss(&self, ecs: &mut World, level: usize, point: Point) -> Option<Entity> {
+        let template = self
+            .entities
+            .iter()
+            .find(|t| t.boss == Some(true) && t.levels.contains(&level))?;
+        let mut commands = CommandBuffer::new(ecs);
+        let location = SpawnLocation {
+            point,
+            preferred_entity: Some(EntityType::Enemy),
+            template: None,
+        };
+        let boss = self.spawn(&location, template, &mut commands);
+        commands.flush(ecs);
+        Some(boss)
+    }
+
+    /// Spawns the template called `name` at `point`, along with anything it carries.
+    pub fn spawn_named(
+        &self,
+        name: &str,
+        point: Point,
+        commands: &mut CommandBuffer,
+    ) -> Option<Entity> {
+        let template = self.entities.iter().find(|t| t.name == name)?;
+        let location = SpawnLocation {
+            point,
+            preferred_entity: None,
+            template: None,
+        };
+        Some(self.spawn(&location, template, commands))
+    }
+
+    fn weighted_pool(&self, level: usize, theme: &str, entity_type: EntityType) -> Vec<&Template> {
+        let mut pool = Vec::new();
         self.entities
             .iter()
-            .filter(|e| e.levels.contains(&level))
+            .filter(|e| e.levels.contains(&level) && e.entity_type == entity_type)
+            .filter(|e| e.themes.as_ref().is_none_or(|themes| themes.contains(theme)))
             .for_each(|t| {
                 for _ in 0..t.frequency {
-                    available_entities.push(t);
+                    pool.push(t);
                 }
             });
-        let mut commands = CommandBuffer::new(ecs);
-        spawn_locations
-            .iter()
-            .for_each(|pt| match pt.preferred_entity {
-                Some(preferred_entity_type) => {
-                    let filtered_entities = &available_entities
-                        .clone()
-                        .into_iter()
-                        .filter(|template| template.entity_type == preferred_entity_type)
-                        .collect::<Vec<&Template>>();
-
-                    if let Some(entity) = rng.random_slice_entry(filtered_entities) {
-                        self.spawner.spawn_entity(pt, entity, &mut commands);
-                    }
-                }
-                None => {
-                    if let Some(entity) = rng.random_slice_entry(&available_entities) {
-                        self.spawner.spawn_entity(pt, entity, &mut commands);
-                    }
-                }
+        pool
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_from_budget(
+        &self,
+        pt: &SpawnLocation,
+        template: &Template,
+        allowance: i32,
+        budget: &mut SpawnBudget,
+        map: &Map,
+        taken: &mut HashSet<Point>,
+        rng: &mut RandomNumberGenerator,
+        commands: &mut CommandBuffer,
+    ) {
+        match template.entity_type {
+            EntityType::Item => {
+                self.spawn(pt, template, commands);
+                budget.items -= 1;
+            }
+            EntityType::Trap | EntityType::Prop => {
+                self.spawn(pt, template, commands);
+            }
+            EntityType::Enemy if template.group.is_some() => {
+                budget.threat -=
+                    self.spawn_group(pt, template, allowance, map, taken, rng, commands);
+            }
+            EntityType::Enemy => {
+                self.spawn(pt, template, commands);
+                budget.threat -= template.threat();
+            }
+        }
+    }
+
+    fn leader_of<'a>(&'a self, template: &'a Template) -> &'a Template {
+        template
+            .group
+            .as_ref()
+            .and_then(|group| group.leader.as_ref())
+            .and_then(|name| self.entities.iter().find(|t| &t.name == name))
+            .unwrap_or(template)
+    }
+
+    fn spawn(&self, pt: &SpawnLocation, template: &Template, commands: &mut CommandBuffer) -> Entity {
+        let entity = self.spawner.spawn_entity(pt, template, commands);
+        if let Some(carries) = &template.carries {
+            carries
+                .iter()
+                .filter_map(|name| self.entities.iter().find(|t| &t.name == name))
+                .for_each(|item_template| {
+                    let item = self.spawner.spawn_entity(pt, item_template, commands);
+                    commands.remove_component::<Point>(item);
+                    commands.add_component(item, Carried(entity));
+                });
+        }
+        entity
+    }
+
+    /// Spawns a pack around `pt`, with no more members than `allowance`
+    /// threat can pay for. Returns the threat actually spent.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_group(
+        &self,
+        pt: &SpawnLocation,
+        template: &Template,
+        allowance: i32,
+        map: &Map,
+        taken: &mut HashSet<Point>,
+        rng: &mut RandomNumberGenerator,
+        commands: &mut CommandBuffer,
+    ) -> i32 {
+        let group = template.group.as_ref().unwrap();
+        let leader_template = self.leader_of(template);
+        let leader = self.spawn(pt, leader_template, commands);
+        commands.add_component(leader, Pack { leader });
+        let mut spent = leader_template.threat();
+
+        let affordable = ((allowance - spent).max(0) / template.threat().max(1)) as usize;
+        let size = rng.range(group.min, group.max + 1) as usize;
+        let members = size.saturating_sub(1).min(affordable);
+        free_tiles_around(pt.point, members, map, taken)
+            .into_iter()
+            .for_each(|point| {
+                let location = SpawnLocation {
+                    point,
+                    preferred_entity: pt.preferred_entity,
+                    template: None,
+                };
+                let member = self.spawn(&location, template, commands);
+                commands.add_component(member, Pack { leader });
+                spent += template.threat();
             });
-        commands.flush(ecs);
+        spent
+    }
+}
+
+fn add_ability(entity: Entity, ability: &Ability, commands: &mut CommandBuffer) {
+    match ability {
+        Ability::PoisonOnHit { damage, turns } => commands.add_component(
+            entity,
+            PoisonOnHit {
+                damage: *damage,
+                turns: *turns,
+            },
+        ),
+        Ability::LifeDrain => commands.add_component(entity, LifeDrain),
+        Ability::Regeneration(amount) => commands.add_component(entity, Regenerates(*amount)),
+        Ability::SummonMinions {
+            minion,
+            count,
+            cooldown,
+            limit,
+        } => commands.add_component(
+            entity,
+            SummonsMinions {
+                minion: minion.clone(),
+                count: *count,
+                cooldown: *cooldown,
+                ready_in: 0,
+                limit: *limit,
+                minions: Vec::new(),
+            },
+        ),
+        Ability::BreathAttack {
+            damage,
+            range,
+            cooldown,
+        } => commands.add_component(
+            entity,
+            BreathAttack {
+                damage: *damage,
+                range: *range,
+                cooldown: *cooldown,
+                ready_in: 0,
+            },
+        ),
+        Ability::SplitOnDeath { into, count } => commands.add_component(
+            entity,
+            SplitsOnDeath {
+                into: into.clone(),
+                count: *count,
+            },
+        ),
+        Ability::Swimmer => commands.add_component(entity, Swimmer),
     }
 }
 
+/// Finds up to `count` free floor tiles packed closely around `origin`,
+/// marking them as taken.
+pub fn free_tiles_around(
+    origin: Point,
+    count: usize,
+    map: &Map,
+    taken: &mut HashSet<Point>,
+) -> Vec<Point> {
+    const MAX_GROUP_RADIUS: f32 = 3.0;
+    let mut found = Vec::new();
+    let mut visited = HashSet::from([origin]);
+    let mut frontier = VecDeque::from([origin]);
+    while let Some(pt) = frontier.pop_front() {
+        for delta in [
+            Point::new(-1, 0),
+            Point::new(1, 0),
+            Point::new(0, -1),
+            Point::new(0, 1),
+        ] {
+            if found.len() >= count {
+                return found;
+            }
+            let next = pt + delta;
+            if !visited.insert(next)
+                || !map.can_enter_tile(next)
+                || DistanceAlg::Pythagoras.distance2d(origin, next) > MAX_GROUP_RADIUS
+            {
+                continue;
+            }
+            if taken.insert(next) {
+                found.push(next);
+            }
+            frontier.push_back(next);
+        }
+    }
+    found
+}
+
 #[cfg(test)]
 pub mod test {
 
@@ -210,6 +590,19 @@ pub mod test {
                 )])),
                 hp: None,
                 base_damage: None,
+                description: None,
+                behaviour: None,
+                flee_below: None,
+                carries: None,
+                loot: None,
+                group: None,
+                threat: None,
+                boss: None,
+                abilities: None,
+                trap: None,
+                themes: None,
+                color: None,
+                light: None,
             },
             Template {
                 entity_type: EntityType::Item,
@@ -223,6 +616,19 @@ pub mod test {
                 )])),
                 hp: None,
                 base_damage: None,
+                description: None,
+                behaviour: None,
+                flee_below: None,
+                carries: None,
+                loot: None,
+                group: None,
+                threat: None,
+                boss: None,
+                abilities: None,
+                trap: None,
+                themes: None,
+                color: None,
+                light: None,
             },
             Template {
                 entity_type: EntityType::Item,
@@ -236,11 +642,30 @@ pub mod test {
                 )])),
                 hp: None,
                 base_damage: None,
+                description: None,
+                behaviour: None,
+                flee_below: None,
+                carries: None,
+                loot: None,
+                group: None,
+                threat: None,
+                boss: None,
+                abilities: None,
+                trap: None,
+                themes: None,
+                color: None,
+                light: None,
             },
         ];
         mock_loader
             .expect_load_entities()
             .returning(move || entities.clone());
+        mock_loader
+            .expect_load_budget()
+            .returning(|| SpawnBudgetConfig {
+                items: 3,
+                ..Default::default()
+            });
         mock_spawner
             .expect_spawn_entity()
             .with(
@@ -248,7 +673,7 @@ pub mod test {
                 predicate::always(),
                 predicate::always(),
             )
-            .returning(|_, _, _| ())
+            .returning(|_, _, _| World::default().push(()))
             .times(3);
         let templates = TemplateSpawner::new(&mock_loader, mock_spawner);
         let mut world = World::default();
@@ -257,20 +682,256 @@ pub mod test {
             &mut world,
             &mut rng,
             0,
+            "dungeon",
+            Difficulty::Normal,
             &[
                 SpawnLocation {
                     point: Point::new(1, 1),
                     preferred_entity: None,
+                    template: None,
                 },
                 SpawnLocation {
                     point: Point::new(2, 2),
                     preferred_entity: None,
+                    template: None,
                 },
                 SpawnLocation {
                     point: Point::new(3, 3),
                     preferred_entity: None,
+                    template: None,
                 },
             ],
+            &Map::default(),
+        );
+    }
+
+    #[test]
+    fn should_spawn_a_whole_group_around_one_location() {
+        let mut mock_spawner = MockCanSpawnEntities::new();
+        let mut mock_loader = MockCanLoadEntities::new();
+        let goblin = Template {
+            entity_type: EntityType::Enemy,
+            levels: HashSet::from([0]),
+            frequency: 1,
+            name: String::from("Test Goblin"),
+            glyph: 'g',
+            provides: None,
+            hp: Some(1),
+            base_damage: Some(1),
+            description: None,
+            behaviour: None,
+            flee_below: None,
+            carries: None,
+            loot: None,
+            group: Some(GroupSpawn {
+                min: 4,
+                max: 4,
+                leader: Some(String::from("Test Chief")),
+            }),
+            threat: None,
+            boss: None,
+            abilities: None,
+            trap: None,
+            themes: None,
+            color: None,
+            light: None,
+        };
+        let chief = Template {
+            levels: HashSet::new(),
+            name: String::from("Test Chief"),
+            group: None,
+            ..goblin.clone()
+        };
+        let entities = vec![goblin, chief];
+        mock_loader
+            .expect_load_entities()
+            .returning(move || entities.clone());
+        mock_loader
+            .expect_load_budget()
+            .returning(|| SpawnBudgetConfig {
+                threat: 10,
+                ..Default::default()
+            });
+        mock_spawner
+            .expect_spawn_entity()
+            .withf(|_, template, _| template.name == "Test Chief")
+            .returning(|_, _, commands| commands.push(()))
+            .times(1);
+        mock_spawner
+            .expect_spawn_entity()
+            .withf(|pt, template, _| {
+                template.name == "Test Goblin"
+                    && DistanceAlg::Pythagoras.distance2d(pt.point, Point::new(10, 10)) <= 3.0
+            })
+            .returning(|_, _, commands| commands.push(()))
+            .times(3);
+        let templates = TemplateSpawner::new(&mock_loader, mock_spawner);
+        let mut world = World::default();
+        let mut rng = RandomNumberGenerator::new();
+        templates.spawn_entities(
+            &mut world,
+            &mut rng,
+            0,
+            "dungeon",
+            Difficulty::Normal,
+            &[SpawnLocation {
+                point: Point::new(10, 10),
+                preferred_entity: None,
+                template: None,
+            }],
+            &Map::default(),
+        );
+    }
+
+    #[test]
+    fn should_load_the_template_file() {
+        let loader = FileEntityLoader::new(String::from("resources/template.ron"));
+        let entities = loader.load_entities();
+        assert!(entities.iter().any(|t| t.entity_type == EntityType::Enemy));
+        assert!(entities.iter().any(|t| t.entity_type == EntityType::Item));
+    }
+
+    #[test]
+    fn should_only_refer_to_templates_that_exist() {
+        let loader = FileEntityLoader::new(String::from("resources/template.ron"));
+        let entities = loader.load_entities();
+        let names: HashSet<&str> = entities.iter().map(|t| t.name.as_str()).collect();
+
+        entities.iter().for_each(|template| {
+            let mut references: Vec<&str> = Vec::new();
+            if let Some(carries) = &template.carries {
+                references.extend(carries.iter().map(String::as_str));
+            }
+            if let Some(loot) = &template.loot {
+                references.extend(loot.iter().map(|entry| entry.item.as_str()));
+            }
+            if let Some(leader) = template.group.as_ref().and_then(|g| g.leader.as_ref()) {
+                references.push(leader);
+            }
+            for ability in template.abilities.iter().flatten() {
+                match ability {
+                    Ability::SummonMinions { minion, .. } => references.push(minion),
+                    Ability::SplitOnDeath { into, .. } => references.push(into),
+                    _ => {}
+                }
+            }
+
+            references.iter().for_each(|name| {
+                assert!(
+                    names.contains(name),
+                    "{} refers to unknown template {}",
+                    template.name,
+                    name
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn should_turn_template_abilities_into_components() {
+        let loader = FileEntityLoader::new(String::from("resources/template.ron"));
+        let templates = TemplateSpawner::new(&loader, Spawner::default());
+        let mut ecs = World::default();
+        let mut commands = CommandBuffer::new(&ecs);
+        let warlord = templates
+            .spawn_named("Ettin Warlord", Point::new(1, 1), &mut commands)
+            .unwrap();
+        commands.flush(&mut ecs);
+
+        let entry = ecs.entry(warlord).unwrap();
+        assert!(entry.get_component::<LifeDrain>().is_ok());
+        assert_eq!(entry.get_component::<BreathAttack>().unwrap().range, 4);
+        assert!(entry.get_component::<Boss>().is_ok());
+        assert!(entry.get_component::<Regenerates>().is_err());
+    }
+
+    #[test]
+    fn should_spawn_traps_hidden() {
+        let loader = FileEntityLoader::new(String::from("resources/template.ron"));
+        let templates = TemplateSpawner::new(&loader, Spawner::default());
+        let mut ecs = World::default();
+        let mut commands = CommandBuffer::new(&ecs);
+        let trap = templates
+            .spawn_named("Spike Trap", Point::new(1, 1), &mut commands)
+            .unwrap();
+        commands.flush(&mut ecs);
+
+        let entry = ecs.entry(trap).unwrap();
+        assert!(entry.get_component::<Hidden>().is_ok());
+        assert_eq!(
+            entry.get_component::<Trap>().unwrap().effect,
+            TrapEffect::Spikes { damage: 2 }
+        );
+        assert!(entry.get_component::<Item>().is_err());
+    }
+
+    #[test]
+    fn should_only_draw_templates_that_belong_in_the_theme() {
+        let mut mock_spawner = MockCanSpawnEntities::new();
+        let mut mock_loader = MockCanLoadEntities::new();
+        let potion = Template {
+            entity_type: EntityType::Item,
+            levels: HashSet::from([0]),
+            frequency: 1,
+            name: String::from("Test Potion"),
+            glyph: '!',
+            provides: None,
+            hp: None,
+            base_damage: None,
+            description: None,
+            behaviour: None,
+            flee_below: None,
+            carries: None,
+            loot: None,
+            group: None,
+            threat: None,
+            boss: None,
+            abilities: None,
+            trap: None,
+            themes: None,
+            color: None,
+            light: None,
+        };
+        let relic = Template {
+            name: String::from("Test Relic"),
+            frequency: 10,
+            themes: Some(HashSet::from([String::from("crypt")])),
+            ..potion.clone()
+        };
+        let entities = vec![potion, relic];
+        mock_loader
+            .expect_load_entities()
+            .returning(move || entities.clone());
+        mock_loader
+            .expect_load_budget()
+            .returning(|| SpawnBudgetConfig {
+                items: 3,
+                ..Default::default()
+            });
+        mock_spawner
+            .expect_spawn_entity()
+            .withf(|_, template, _| template.name == "Test Potion")
+            .returning(|_, _, commands| commands.push(()))
+            .times(3);
+        let templates = TemplateSpawner::new(&mock_loader, mock_spawner);
+        let mut world = World::default();
+        let mut rng = RandomNumberGenerator::new();
+        let spawn_locations: Vec<SpawnLocation> = (1..=3)
+            .map(|x| SpawnLocation {
+                point: Point::new(x, 1),
+                preferred_entity: None,
+                template: None,
+            })
+            .collect();
+
+        templates.spawn_entities(
+            &mut world,
+            &mut rng,
+            0,
+            "dungeon",
+            Difficulty::Normal,
+            &spawn_locations,
+            &Map::default(),
         );
     }
 }
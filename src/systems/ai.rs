@@ -0,0 +1,448 @@
+use std::collections::HashSet;
+
+use legion::systems::CommandBuffer;
+
+use crate::prelude::*;
+
+const WAKE_DISTANCE: f32 = 3.0;
+const PATROL_RADIUS: i32 = 8;
+const RANGED_MIN_DISTANCE: f32 = 2.0;
+const PACK_DISTANCE: f32 = 3.0;
+
+enum Action {
+    Wait,
+    MoveTo(Point),
+    Attack,
+    UseItem(Entity),
+}
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Health)]
+#[read_component(FieldOfView)]
+#[read_component(Brain)]
+#[read_component(Carried)]
+#[read_component(ProvidesHealing)]
+#[read_component(Pack)]
+pub fn ai(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] flow_field: &mut FlowField,
+    #[resource] rng: &mut RandomNumberGenerator,
+) {
+    let (player, player_pos) = <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+        .unwrap();
+    flow_field.update(player_pos, map);
+
+    let mut occupied: HashSet<Point> = <&Point>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    let alerted_packs: HashSet<Entity> = <(&Pack, &FieldOfView)>::query()
+        .iter(ecs)
+        .filter(|(_, fov)| fov.visible_tiles.contains(&player_pos))
+        .map(|(pack, _)| pack.leader)
+        .collect();
+
+    let mut thinkers = <(Entity, &Point, &Brain, &FieldOfView, &Health)>::query();
+    thinkers
+        .iter(ecs)
+        .for_each(|(entity, pos, brain, fov, health)| {
+            let sees_player = fov.visible_tiles.contains(&player_pos);
+            let mut state = next_state(brain, *pos, sees_player.then_some(player_pos), health);
+            let pack = ecs
+                .entry_ref(*entity)
+                .unwrap()
+                .get_component::<Pack>()
+                .ok()
+                .copied();
+            if let Some(pack) = pack {
+                if alerted_packs.contains(&pack.leader)
+                    && !matches!(state, AiState::Hunting { .. } | AiState::Fleeing)
+                {
+                    state = AiState::Hunting {
+                        last_known: player_pos,
+                    };
+                }
+            }
+
+            let healing_potion = if health.current * 2 <= health.max {
+                <(Entity, &Carried)>::query()
+                    .filter(component::<ProvidesHealing>())
+                    .iter(ecs)
+                    .find(|(_, carried)| carried.0 == *entity)
+                    .map(|(potion, _)| *potion)
+            } else {
+                None
+            };
+
+            let action = if let Some(potion) = healing_potion {
+                Action::UseItem(potion)
+            } else {
+                match state {
+                    AiState::Sleeping => Action::Wait,
+                    AiState::Idle => {
+                        match (pack_leader_position(pack, *entity, ecs), brain.behaviour) {
+                            (Some(leader_pos), _)
+                                if DistanceAlg::Pythagoras.distance2d(*pos, leader_pos)
+                                    > PACK_DISTANCE =>
+                            {
+                                step_towards(*pos, leader_pos, map)
+                                    .map(Action::MoveTo)
+                                    .unwrap_or(Action::Wait)
+                            }
+                            (_, Behaviour::Wanderer) => Action::MoveTo(random_step(*pos, rng)),
+                            _ => Action::Wait,
+                        }
+                    }
+                    AiState::Patrolling { origin, target } => {
+                        let target = if *pos == target {
+                            choose_patrol_target(origin, map, rng)
+                        } else {
+                            target
+                        };
+                        state = AiState::Patrolling { origin, target };
+                        match step_towards(*pos, target, map) {
+                            Some(step) => Action::MoveTo(step),
+                            None => {
+                                state = AiState::Patrolling {
+                                    origin,
+                                    target: *pos,
+                                };
+                                Action::Wait
+                            }
+                        }
+                    }
+                    AiState::Hunting { .. } if sees_player => match brain.behaviour {
+                        Behaviour::Ranged { range } => {
+                            let distance = DistanceAlg::Pythagoras.distance2d(*pos, player_pos);
+                            if distance < RANGED_MIN_DISTANCE {
+                                flee(*pos, flow_field, map, &occupied).unwrap_or(Action::Attack)
+                            } else if distance <= range as f32 {
+                                Action::Attack
+                            } else {
+                                chase(*pos, player_pos, flow_field, map, &occupied)
+                            }
+                        }
+                        _ => chase(*pos, player_pos, flow_field, map, &occupied),
+                    },
+                    AiState::Hunting { last_known } => match step_towards(*pos, last_known, map) {
+                        Some(step) => Action::MoveTo(step),
+                        None => {
+                            state = settle(brain, *pos);
+                            Action::Wait
+                        }
+                    },
+                    AiState::Fleeing => run_away(*pos, player_pos, flow_field, map, &occupied),
+                }
+            };
+
+            match action {
+                Action::Wait => {}
+                Action::UseItem(item) => {
+                    commands.push((
+                        (),
+                        ActivateItem {
+                            used_by: *entity,
+                            item,
+                        },
+                    ));
+                }
+                Action::Attack => {
+                    if sees_player {
+                        commands.push((
+                            (),
+                            WantsToAttack {
+                                attacker: *entity,
+                                victim: player,
+                            },
+                        ));
+                    }
+                }
+                Action::MoveTo(destination) => {
+                    if destination == player_pos {
+                        commands.push((
+                            (),
+                            WantsToAttack {
+                                attacker: *entity,
+                                victim: player,
+                            },
+                        ));
+                    } else if map.is_closed_door(destination) {
+                        commands.push((
+                            (),
+                            WantsToMove {
+                                entity: *entity,
+                                destination,
+                            },
+                        ));
+                    } else if !occupied.contains(&destination) && map.can_enter_tile(destination) {
+                        occupied.remove(pos);
+                        occupied.insert(destination);
+                        commands.push((
+                            (),
+                            WantsToMove {
+                                entity: *entity,
+                                destination,
+                            },
+                        ));
+                    }
+                }
+            }
+
+            if state != brain.state {
+                commands.add_component(*entity, Brain { state, ..*brain });
+            }
+        });
+}
+
+/// Works out what a monster wants to do this turn, given whether it can see
+/// the player and how badly it is hurt.
+pub fn next_state(
+    brain: &Brain,
+    position: Point,
+    player: Option<Point>,
+    health: &Health,
+) -> AiState {
+    let wounded = health.current < health.max;
+    match player {
+        Some(player_pos) => {
+            if brain.flee_below > 0 && health.current * 100 <= health.max * brain.flee_below {
+                return AiState::Fleeing;
+            }
+            if brain.state == AiState::Sleeping
+                && !wounded
+                && DistanceAlg::Pythagoras.distance2d(position, player_pos) > WAKE_DISTANCE
+            {
+                return AiState::Sleeping;
+            }
+            AiState::Hunting {
+                last_known: player_pos,
+            }
+        }
+        None => match brain.state {
+            AiState::Sleeping if wounded => AiState::Idle,
+            AiState::Fleeing => settle(brain, position),
+            AiState::Hunting { last_known } if last_known == position => settle(brain, position),
+            state => state,
+        },
+    }
+}
+
+/// Where an idle pack member's leader is, so that it can stay close by.
+fn pack_leader_position(pack: Option<Pack>, entity: Entity, ecs: &SubWorld) -> Option<Point> {
+    let leader = pack?.leader;
+    if leader == entity {
+        return None;
+    }
+    ecs.entry_ref(leader)
+        .ok()
+        .and_then(|entry| entry.get_component::<Point>().ok().copied())
+}
+
+fn settle(brain: &Brain, position: Point) -> AiState {
+    match brain.behaviour {
+        Behaviour::Sleeper => AiState::Idle,
+        behaviour => Brain::resting_state(behaviour, position),
+    }
+}
+
+fn chase(
+    position: Point,
+    player_pos: Point,
+    flow_field: &FlowField,
+    map: &Map,
+    occupied: &HashSet<Point>,
+) -> Action {
+    if is_adjacent(position, player_pos) {
+        return Action::Attack;
+    }
+    flow_field
+        .step_towards(position, map, occupied)
+        .map(Action::MoveTo)
+        .unwrap_or(Action::Wait)
+}
+
+/// Flees from the player, turning to fight only when cornered with the player
+/// close enough to hit.
+fn run_away(
+    position: Point,
+    player_pos: Point,
+    flow_field: &FlowField,
+    map: &Map,
+    occupied: &HashSet<Point>,
+) -> Action {
+    match flee(position, flow_field, map, occupied) {
+        Some(action) => action,
+        None if is_adjacent(position, player_pos) => Action::Attack,
+        None => Action::Wait,
+    }
+}
+
+fn is_adjacent(position: Point, player_pos: Point) -> bool {
+    DistanceAlg::Pythagoras.distance2d(position, player_pos) < 1.2
+}
+
+fn flee(
+    position: Point,
+    flow_field: &FlowField,
+    map: &Map,
+    occupied: &HashSet<Point>,
+) -> Option<Action> {
+    flow_field
+        .step_away(position, map, occupied)
+        .map(Action::MoveTo)
+}
+
+fn step_towards(position: Point, target: Point, map: &Map) -> Option<Point> {
+    if position == target {
+        return None;
+    }
+    let path = a_star_search(
+        map.point2d_to_index(position),
+        map.point2d_to_index(target),
+        map,
+    );
+    if path.success && path.steps.len() > 1 {
+        Some(map.index_to_point2d(path.steps[1]))
+    } else {
+        None
+    }
+}
+
+fn random_step(position: Point, rng: &mut RandomNumberGenerator) -> Point {
+    let delta = match rng.range(0, 4) {
+        0 => Point::new(-1, 0),
+        1 => Point::new(1, 0),
+        2 => Point::new(0, -1),
+        _ => Point::new(0, 1),
+    };
+    position + delta
+}
+
+fn choose_patrol_target(origin: Point, map: &Map, rng: &mut RandomNumberGenerator) -> Point {
+    for _ in 0..10 {
+        let target = origin
+            + Point::new(
+                rng.range(-PATROL_RADIUS, PATROL_RADIUS + 1),
+                rng.range(-PATROL_RADIUS, PATROL_RADIUS + 1),
+            );
+        if map.can_enter_tile(target) {
+            return target;
+        }
+    }
+    origin
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    fn brain(behaviour: Behaviour) -> Brain {
+        Brain::new(behaviour, Point::new(10, 10), 50)
+    }
+
+    fn health(current: i32) -> Health {
+        Health { current, max: 10 }
+    }
+
+    #[test]
+    fn should_hunt_the_player_when_seen() {
+        let state = next_state(
+            &brain(Behaviour::Guard),
+            Point::new(10, 10),
+            Some(Point::new(14, 10)),
+            &health(10),
+        );
+        assert_eq!(
+            state,
+            AiState::Hunting {
+                last_known: Point::new(14, 10)
+            }
+        );
+    }
+
+    #[test]
+    fn should_keep_sleeping_until_the_player_is_close() {
+        let sleeper = brain(Behaviour::Sleeper);
+        let far = next_state(
+            &sleeper,
+            Point::new(10, 10),
+            Some(Point::new(16, 10)),
+            &health(10),
+        );
+        let near = next_state(
+            &sleeper,
+            Point::new(10, 10),
+            Some(Point::new(12, 10)),
+            &health(10),
+        );
+        assert_eq!(far, AiState::Sleeping);
+        assert_eq!(
+            near,
+            AiState::Hunting {
+                last_known: Point::new(12, 10)
+            }
+        );
+    }
+
+    #[test]
+    fn should_flee_when_badly_hurt() {
+        let state = next_state(
+            &brain(Behaviour::Guard),
+            Point::new(10, 10),
+            Some(Point::new(11, 10)),
+            &health(5),
+        );
+        assert_eq!(state, AiState::Fleeing);
+    }
+
+    fn dead_end() -> Map {
+        let mut map = Map::new(10, 10);
+        map.tiles.iter_mut().for_each(|t| *t = TileType::Wall);
+        for x in 1..=6 {
+            let idx = map.map_idx(x, 5);
+            map.tiles[idx] = TileType::Floor;
+        }
+        map
+    }
+
+    #[test]
+    fn should_only_fight_back_when_cornered_next_to_the_player() {
+        let map = dead_end();
+        let cornered = Point::new(1, 5);
+        let run_from = |player_pos: Point| {
+            let mut flow_field = FlowField::new();
+            flow_field.update(player_pos, &map);
+            let occupied = HashSet::from([cornered, player_pos]);
+            run_away(cornered, player_pos, &flow_field, &map, &occupied)
+        };
+
+        assert!(matches!(run_from(Point::new(4, 5)), Action::Wait));
+        assert!(matches!(run_from(Point::new(2, 5)), Action::Attack));
+    }
+
+    #[test]
+    fn should_give_up_at_the_last_known_position() {
+        let mut patroller = brain(Behaviour::Patroller);
+        patroller.state = AiState::Hunting {
+            last_known: Point::new(3, 3),
+        };
+        let state = next_state(&patroller, Point::new(3, 3), None, &health(10));
+        assert_eq!(
+            state,
+            AiState::Patrolling {
+                origin: Point::new(3, 3),
+                target: Point::new(3, 3)
+            }
+        );
+    }
+}
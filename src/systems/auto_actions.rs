@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+
+use legion::systems::CommandBuffer;
+
+use crate::prelude::*;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Item)]
+#[read_component(FieldOfView)]
+#[write_component(Health)]
+#[read_component(Resting)]
+#[read_component(AutoExploring)]
+#[read_component(Travelling)]
+#[read_component(Slowed)]
+pub fn auto_actions(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] mouse_click: &MouseClick,
+    #[resource] map: &Map,
+    #[resource] turn_state: &mut TurnState,
+) {
+    if key.is_some() || mouse_click.0 {
+        return;
+    }
+
+    let (player, player_pos) = <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+        .unwrap();
+
+    let entry = ecs.entry_ref(player).unwrap();
+    let resting = entry.get_component::<Resting>().is_ok();
+    let exploring = entry.get_component::<AutoExploring>().ok().cloned();
+    let travelling = entry.get_component::<Travelling>().ok().cloned();
+    let fov = entry.get_component::<FieldOfView>().unwrap().clone();
+
+    if !resting && exploring.is_none() && travelling.is_none() {
+        return;
+    }
+
+    if let Some(travelling) = travelling {
+        continue_travelling(
+            player, player_pos, travelling, &fov, map, ecs, commands, turn_state,
+        );
+        return;
+    }
+
+    if enemy_in_view(player, ecs) {
+        stop_auto_actions(player, commands);
+        return;
+    }
+
+    if resting {
+        let fully_healed = ecs
+            .entry_ref(player)
+            .unwrap()
+            .get_component::<Health>()
+            .map(|health| health.current >= health.max)
+            .unwrap_or(true);
+        if fully_healed {
+            stop_auto_actions(player, commands);
+            return;
+        }
+        rest(player, ecs);
+        *turn_state = TurnState::PlayerTurn;
+    } else if let Some(exploring) = exploring {
+        let visible_items = visible_items(&fov, ecs);
+        if visible_items
+            .iter()
+            .any(|item| !exploring.known_items.contains(item))
+        {
+            stop_auto_actions(player, commands);
+            return;
+        }
+
+        match next_explore_step(player_pos, &fov, map) {
+            Some(destination) => {
+                commands.push((
+                    (),
+                    WantsToMove {
+                        entity: player,
+                        destination,
+                    },
+                ));
+                *turn_state = TurnState::PlayerTurn;
+            }
+            None => stop_auto_actions(player, commands),
+        }
+    }
+}
+
+pub fn start_exploring(player: Entity, ecs: &SubWorld, commands: &mut CommandBuffer) {
+    let fov = ecs
+        .entry_ref(player)
+        .unwrap()
+        .get_component::<FieldOfView>()
+        .unwrap()
+        .clone();
+    commands.add_component(
+        player,
+        AutoExploring {
+            known_items: visible_items(&fov, ecs),
+        },
+    );
+}
+
+pub fn start_travelling(
+    player: Entity,
+    target: Point,
+    ecs: &SubWorld,
+    map: &Map,
+    commands: &mut CommandBuffer,
+) {
+    let entry = ecs.entry_ref(player).unwrap();
+    let player_pos = *entry.get_component::<Point>().unwrap();
+    let fov = entry.get_component::<FieldOfView>().unwrap().clone();
+
+    let target_idx = match map.try_idx(target) {
+        Some(idx) => idx,
+        None => return,
+    };
+    let known = map.revealed_tiles[target_idx] || fov.visible_tiles.contains(&target);
+    if target == player_pos || !known || !map.can_enter_tile(target) {
+        return;
+    }
+
+    let path = a_star_search(map.point2d_to_index(player_pos), target_idx, map);
+    if !path.success {
+        return;
+    }
+
+    commands.add_component(
+        player,
+        Travelling {
+            path: path
+                .steps
+                .iter()
+                .skip(1)
+                .map(|idx| map.index_to_point2d(*idx))
+                .collect(),
+            known_enemies: visible_enemies(&fov, ecs),
+        },
+    );
+}
+
+pub fn stop_auto_actions(player: Entity, commands: &mut CommandBuffer) {
+    commands.remove_component::<Resting>(player);
+    commands.remove_component::<AutoExploring>(player);
+    commands.remove_component::<Travelling>(player);
+}
+
+pub fn is_auto_acting(player: Entity, ecs: &SubWorld) -> bool {
+    let entry = ecs.entry_ref(player).unwrap();
+    entry.get_component::<Resting>().is_ok()
+        || entry.get_component::<AutoExploring>().is_ok()
+        || entry.get_component::<Travelling>().is_ok()
+}
+
+pub fn enemy_in_view(player: Entity, ecs: &SubWorld) -> bool {
+    let entry = ecs.entry_ref(player).unwrap();
+    let fov = entry.get_component::<FieldOfView>().unwrap();
+    <&Point>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .any(|pos| fov.visible_tiles.contains(pos))
+}
+
+/// Spends a turn doing nothing. With no enemies in sight the player catches
+/// their breath and recovers a point of health.
+pub fn rest(player: Entity, ecs: &mut SubWorld) {
+    if enemy_in_view(player, ecs) {
+        return;
+    }
+    if let Ok(health) = ecs.entry_mut(player).unwrap().get_component_mut::<Health>() {
+        health.current = i32::min(health.max, health.current + 1);
+    }
+}
+
+fn visible_items(fov: &FieldOfView, ecs: &SubWorld) -> HashSet<Entity> {
+    <(Entity, &Point)>::query()
+        .filter(component::<Item>())
+        .iter(ecs)
+        .filter(|(_, pos)| fov.visible_tiles.contains(pos))
+        .map(|(entity, _)| *entity)
+        .collect()
+}
+
+fn visible_enemies(fov: &FieldOfView, ecs: &SubWorld) -> HashSet<Entity> {
+    <(Entity, &Point)>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .filter(|(_, pos)| fov.visible_tiles.contains(pos))
+        .map(|(entity, _)| *entity)
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn continue_travelling(
+    player: Entity,
+    player_pos: Point,
+    mut travelling: Travelling,
+    fov: &FieldOfView,
+    map: &Map,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    turn_state: &mut TurnState,
+) {
+    let visible_enemies = visible_enemies(fov, ecs);
+    if visible_enemies
+        .iter()
+        .any(|enemy| !travelling.known_enemies.contains(enemy))
+    {
+        stop_auto_actions(player, commands);
+        return;
+    }
+
+    let next_step = match travelling.path.first() {
+        Some(step) => *step,
+        None => {
+            stop_auto_actions(player, commands);
+            return;
+        }
+    };
+
+    let blocked = <&Point>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .any(|pos| *pos == next_step);
+    if blocked || DistanceAlg::Manhattan.distance2d(player_pos, next_step) > 1.0 {
+        stop_auto_actions(player, commands);
+        return;
+    }
+
+    // Walking into a closed door only opens it, and wading out of shallow water
+    // takes a turn before moving, so in either case the step is taken again
+    // next turn.
+    let slowed = ecs
+        .entry_ref(player)
+        .is_ok_and(|entry| entry.get_component::<Slowed>().is_ok());
+    if !map.is_closed_door(next_step) && !slowed {
+        travelling.path.remove(0);
+    }
+    travelling.known_enemies = visible_enemies;
+    commands.push((
+        (),
+        WantsToMove {
+            entity: player,
+            destination: next_step,
+        },
+    ));
+    if travelling.path.is_empty() {
+        commands.remove_component::<Travelling>(player);
+    } else {
+        commands.add_component(player, travelling);
+    }
+    *turn_state = TurnState::PlayerTurn;
+}
+
+fn next_explore_step(player_pos: Point, fov: &FieldOfView, map: &Map) -> Option<Point> {
+    let unexplored: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| {
+            let pt = map.index_to_point2d(*idx);
+            !map.revealed_tiles[*idx] && !fov.visible_tiles.contains(&pt) && map.can_enter_tile(pt)
+        })
+        .collect();
+    if unexplored.is_empty() {
+        return None;
+    }
+
+    let dijkstra_map = DijkstraMap::new(map.width, map.height, &unexplored, map, 1024.0);
+    let player_idx = map.point2d_to_index(player_pos);
+    if dijkstra_map.map[player_idx] == f32::MAX {
+        return None;
+    }
+
+    DijkstraMap::find_lowest_exit(&dijkstra_map, player_idx, map)
+        .map(|idx| map.index_to_point2d(idx))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::movement::movement_system;
+    use super::*;
+
+    #[test]
+    fn should_travel_on_through_shallow_water() {
+        let mut map = Map::new(10, 3);
+        let water = map.map_idx(2, 1);
+        map.tiles[water] = TileType::ShallowWater;
+        let mut resources = Resources::default();
+        resources.insert(map);
+        resources.insert(Camera::new(Point::new(1, 1)));
+        resources.insert(None::<VirtualKeyCode>);
+        resources.insert(MouseClick(false));
+        resources.insert(TurnState::AwaitingInput);
+        let mut ecs = World::default();
+        let player = ecs.push((
+            Player { map_level: 0 },
+            Point::new(1, 1),
+            FieldOfView::new(8),
+            Travelling {
+                path: vec![Point::new(2, 1), Point::new(3, 1), Point::new(4, 1)],
+                known_enemies: HashSet::new(),
+            },
+        ));
+        let mut schedule = Schedule::builder()
+            .add_system(auto_actions_system())
+            .flush()
+            .add_system(movement_system())
+            .build();
+
+        for _ in 0..4 {
+            schedule.execute(&mut ecs, &mut resources);
+        }
+
+        let entry = ecs.entry(player).unwrap();
+        assert_eq!(entry.get_component::<Point>(), Ok(&Point::new(4, 1)));
+        assert!(entry.get_component::<Travelling>().is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+/// Extra damage an enraged boss deals with every hit.
+const ENRAGE_DAMAGE: i32 = 2;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Health)]
+#[write_component(Boss)]
+#[write_component(Damage)]
+#[write_component(Brain)]
+pub fn boss(ecs: &mut SubWorld) {
+    let player_pos = <&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .copied()
+        .next()
+        .unwrap();
+
+    let mut roared = false;
+    <(&mut Boss, &Health, &mut Damage)>::query()
+        .iter_mut(ecs)
+        .filter(|(boss, health, _)| !boss.enraged && health.current * 2 <= health.max)
+        .for_each(|(boss, _, damage)| {
+            boss.enraged = true;
+            damage.0 += ENRAGE_DAMAGE;
+            roared = true;
+        });
+
+    if roared {
+        <&mut Brain>::query().iter_mut(ecs).for_each(|brain| {
+            brain.state = AiState::Hunting {
+                last_known: player_pos,
+            };
+        });
+    }
+}
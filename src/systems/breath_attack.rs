@@ -0,0 +1,53 @@
+use legion::systems::CommandBuffer;
+
+use super::auto_actions::stop_auto_actions;
+use crate::prelude::*;
+
+/// Breathes on the player from a distance whenever a monster's breath is
+/// ready and the player is in sight and in range. Breathing doesn't use up
+/// the monster's move for the turn.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[write_component(BreathAttack)]
+#[write_component(Health)]
+pub fn breath_attack(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    let (player, player_pos) = <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+        .unwrap();
+
+    let mut damage = 0;
+    <(&Point, &FieldOfView, &mut BreathAttack)>::query()
+        .iter_mut(ecs)
+        .for_each(|(pos, fov, breath)| {
+            if breath.ready_in > 0 {
+                breath.ready_in -= 1;
+            } else if fov.visible_tiles.contains(&player_pos)
+                && DistanceAlg::Pythagoras.distance2d(*pos, player_pos) <= breath.range as f32
+            {
+                damage += breath.damage;
+                breath.ready_in = breath.cooldown;
+            }
+        });
+
+    if damage > 0 {
+        if let Ok(health) = ecs
+            .entry_mut(player)
+            .unwrap()
+            .get_component_mut::<Health>()
+        {
+            health.current -= damage;
+        }
+        stop_auto_actions(player, commands);
+        commands.push((
+            (),
+            ScreenEffects {
+                effect: ScreenEffectsEnum::TakeDamage,
+            },
+        ));
+    }
+}
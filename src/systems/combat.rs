@@ -1,6 +1,16 @@
+use std::collections::HashSet;
+
 use legion::systems::CommandBuffer;
 
-use crate::{prelude::*, score_tracker::ScoreTracker};
+use super::auto_actions::stop_auto_actions;
+use crate::{
+    prelude::*,
+    score_tracker::ScoreTracker,
+    spawner::{
+        roll_loot,
+        template::{free_tiles_around, CanSpawnEntities, Spawner, TemplateSpawner},
+    },
+};
 
 #[system]
 #[read_component(WantsToAttack)]
@@ -8,7 +18,19 @@ use crate::{prelude::*, score_tracker::ScoreTracker};
 #[write_component(Health)]
 #[read_component(Damage)]
 #[read_component(Carried)]
-pub fn combat(ecs: &mut SubWorld, #[resource] score_tracker: &mut ScoreTracker, commands: &mut CommandBuffer) {
+#[read_component(Point)]
+#[read_component(LootTable)]
+#[read_component(PoisonOnHit)]
+#[read_component(LifeDrain)]
+#[read_component(SplitsOnDeath)]
+pub fn combat(
+    ecs: &mut SubWorld,
+    #[resource] map: &Map,
+    #[resource] score_tracker: &mut ScoreTracker,
+    #[resource] templates: &TemplateSpawner<Spawner>,
+    #[resource] rng: &mut RandomNumberGenerator,
+    commands: &mut CommandBuffer,
+) {
     let mut attackers = <(Entity, &WantsToAttack)>::query();
     let victims: Vec<(Entity, Entity, Entity)> = attackers
         .iter(ecs)
@@ -40,6 +62,15 @@ pub fn combat(ecs: &mut SubWorld, #[resource] score_tracker: &mut ScoreTracker,
 
         let final_damage = base_damage + weapon_damage;
 
+        let (poison, drains) = match ecs.entry_ref(*attacker) {
+            Ok(entry) => (
+                entry.get_component::<PoisonOnHit>().ok().copied(),
+                entry.get_component::<LifeDrain>().is_ok(),
+            ),
+            Err(_) => (None, false),
+        };
+
+        let mut killed = false;
         if let Ok(health) = ecs
             .entry_mut(*victim)
             .unwrap()
@@ -47,10 +78,10 @@ pub fn combat(ecs: &mut SubWorld, #[resource] score_tracker: &mut ScoreTracker,
         {
             health.current -= final_damage;
             if health.current < 1 && !is_player {
-                commands.remove(*victim);
-                score_tracker.increase_score(health.max);
+                killed = true;
             }
             if health.current > 0 && is_player {
+                stop_auto_actions(*victim, commands);
                 commands.push(((), 
                     ScreenEffects {
                         effect: ScreenEffectsEnum::TakeDamage
@@ -58,6 +89,118 @@ pub fn combat(ecs: &mut SubWorld, #[resource] score_tracker: &mut ScoreTracker,
                 ));
             }
         }
+        if let Some(poison) = poison.filter(|_| !killed) {
+            commands.add_component(
+                *victim,
+                Poisoned {
+                    damage: poison.damage,
+                    turns: poison.turns,
+                },
+            );
+        }
+        if drains {
+            if let Ok(mut entry) = ecs.entry_mut(*attacker) {
+                if let Ok(health) = entry.get_component_mut::<Health>() {
+                    health.current = i32::min(health.max, health.current + final_damage);
+                }
+            }
+        }
+        if killed {
+            kill_monster(*victim, ecs, map, score_tracker, templates, rng, commands);
+        }
         commands.remove(*message);
     });
 }
+
+/// Removes a slain monster, however it died: the player scores it, and it
+/// drops whatever it carried, rolls its loot table and splits if it can.
+pub(super) fn kill_monster(
+    victim: Entity,
+    ecs: &SubWorld,
+    map: &Map,
+    score_tracker: &mut ScoreTracker,
+    templates: &TemplateSpawner<Spawner>,
+    rng: &mut RandomNumberGenerator,
+    commands: &mut CommandBuffer,
+) {
+    if let Ok(health) = ecs.entry_ref(victim).unwrap().get_component::<Health>() {
+        score_tracker.increase_score(health.max);
+    }
+    commands.remove(victim);
+    drop_carried_items(victim, ecs, commands);
+    drop_loot(victim, ecs, templates, rng, commands);
+    split_on_death(victim, ecs, templates, map, commands);
+}
+
+fn drop_carried_items(victim: Entity, ecs: &SubWorld, commands: &mut CommandBuffer) {
+    let pos = match ecs.entry_ref(victim).unwrap().get_component::<Point>() {
+        Ok(pos) => *pos,
+        Err(_) => return,
+    };
+    <(Entity, &Carried)>::query()
+        .iter(ecs)
+        .filter(|(_, carried)| carried.0 == victim)
+        .for_each(|(item, _)| {
+            commands.remove_component::<Carried>(*item);
+            commands.add_component(*item, pos);
+        });
+}
+
+fn drop_loot(
+    victim: Entity,
+    ecs: &SubWorld,
+    templates: &TemplateSpawner<Spawner>,
+    rng: &mut RandomNumberGenerator,
+    commands: &mut CommandBuffer,
+) {
+    let entry = ecs.entry_ref(victim).unwrap();
+    let (pos, loot) = match (
+        entry.get_component::<Point>(),
+        entry.get_component::<LootTable>(),
+    ) {
+        (Ok(pos), Ok(loot)) => (*pos, loot),
+        _ => return,
+    };
+
+    let location = SpawnLocation {
+        point: pos,
+        preferred_entity: None,
+        template: None,
+    };
+    roll_loot(&loot.0, rng)
+        .into_iter()
+        .filter_map(|name| templates.entities.iter().find(|t| t.name == name))
+        .for_each(|template| {
+            templates.spawner.spawn_entity(&location, template, commands);
+        });
+}
+
+fn split_on_death(
+    victim: Entity,
+    ecs: &SubWorld,
+    templates: &TemplateSpawner<Spawner>,
+    map: &Map,
+    commands: &mut CommandBuffer,
+) {
+    let entry = ecs.entry_ref(victim).unwrap();
+    let (pos, split) = match (
+        entry.get_component::<Point>(),
+        entry.get_component::<SplitsOnDeath>(),
+    ) {
+        (Ok(pos), Ok(split)) => (*pos, split),
+        _ => return,
+    };
+
+    let mut taken: HashSet<Point> = <&Point>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .copied()
+        .collect();
+    let count = split.count.max(0) as usize;
+    std::iter::once(pos)
+        .chain(free_tiles_around(pos, count.saturating_sub(1), map, &mut taken))
+        .take(count)
+        .for_each(|point| {
+            templates.spawn_named(&split.into, point, commands);
+        });
+}
@@ -5,10 +5,11 @@ use crate::prelude::*;
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(AmuletOfYala)]
+#[read_component(Boss)]
 pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resource] map: &Map) {
     let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
     let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
-    let current_state = turn_state.clone();
+    let current_state = *turn_state;
     let mut new_state = match current_state {
         TurnState::AwaitingInput => return,
         TurnState::PlayerTurn => TurnState::MonsterTurn,
@@ -17,13 +18,14 @@ pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resour
     };
 
     let amulet_default = Point::new(-1, -1);
-    let amulet_pos = amulet.iter(ecs).nth(0).unwrap_or(&amulet_default);
+    let amulet_pos = amulet.iter(ecs).next().unwrap_or(&amulet_default);
+    let boss_alive = <&Boss>::query().iter(ecs).next().is_some();
 
     player_hp.iter(ecs).for_each(|(hp, pos)| {
         if hp.current < 1 {
             new_state = TurnState::GameOver;
         }
-        if pos == amulet_pos {
+        if pos == amulet_pos && !boss_alive {
             new_state = TurnState::Victory;
         }
         let idx = map.point2d_to_index(*pos);
@@ -5,19 +5,42 @@ use crate::prelude::*;
 #[read_component(Render)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
-    let mut renderables = <(&Point, &Render)>::query();
+#[read_component(Hidden)]
+pub fn entity_render(
+    ecs: &SubWorld,
+    #[resource] camera: &Camera,
+    #[resource] map: &Map,
+    #[resource] entity_memory: &EntityMemory,
+) {
+    let mut renderables = <(&Point, &Render)>::query().filter(!component::<Hidden>());
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(1);
     let offset = Point::new(camera.left_x, camera.top_y);
 
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let player_fov = fov.iter(ecs).next().unwrap();
+
+    entity_memory
+        .remembered()
+        .filter(|memory| {
+            !player_fov.visible_tiles.contains(&memory.position)
+                && map
+                    .try_idx(memory.position)
+                    .map(|idx| map.revealed_tiles[idx])
+                    .unwrap_or(false)
+        })
+        .for_each(|memory| {
+            draw_batch.set(
+                memory.position - offset,
+                ColorPair::new(DARK_GRAY, BLACK),
+                memory.glyph,
+            );
+        });
 
     renderables
         .iter(ecs)
-        .filter(|(position, _)| player_fov.visible_tiles.contains(&position))
+        .filter(|(position, _)| player_fov.visible_tiles.contains(position))
         .for_each(|(position, render)| {
             draw_batch.set(*position - offset, render.color, render.glyph);
         });
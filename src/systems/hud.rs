@@ -8,13 +8,18 @@ use crate::{prelude::*, score_tracker::ScoreTracker};
 #[read_component(Name)]
 #[read_component(ScreenEffects)]
 #[read_component(ScoreTracker)]
-pub fn hud(ecs: &SubWorld, #[resource] score_tracker: &ScoreTracker) {
+#[read_component(Poisoned)]
+pub fn hud(
+    ecs: &SubWorld,
+    #[resource] score_tracker: &ScoreTracker,
+    #[resource] difficulty: &Difficulty,
+) {
     let mut health_query = <&Health>::query().filter(component::<Player>());
-    let player_health = health_query.iter(ecs).nth(0).unwrap();
+    let player_health = health_query.iter(ecs).next().unwrap();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    draw_batch.print_centered(1, "Explore the Dungeon. Cursor keys to move.");
+    draw_batch.print_centered(1, "Explore the Dungeon. Cursor keys or click to move, Space to wait, S to search, R to rest, O to auto-explore, L to look.");
     draw_batch.bar_horizontal(
         Point::zero(),
         SCREEN_WIDTH * 2,
@@ -31,9 +36,22 @@ pub fn hud(ecs: &SubWorld, #[resource] score_tracker: &ScoreTracker) {
         ColorPair::new(WHITE, RED),
     );
 
+    if let Some(poisoned) = <&Poisoned>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    {
+        draw_batch.print_color(
+            Point::new(3, 1),
+            format!("Poisoned ({})", poisoned.turns),
+            ColorPair::new(GREEN, BLACK),
+        );
+    }
+
     let (player, map_level) = <(Entity, &Player)>::query()
         .iter(ecs)
-        .find_map(|(entity, player)| Some((*entity, player.map_level)))
+        .map(|(entity, player)| (*entity, player.map_level))
+        .next()
         .unwrap();
 
     draw_batch.print_color_right(
@@ -56,6 +74,12 @@ pub fn hud(ecs: &SubWorld, #[resource] score_tracker: &ScoreTracker) {
         ColorPair::new(YELLOW, BLACK),
     );
 
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2, 4),
+        format!("Difficulty: {}", difficulty.name()),
+        ColorPair::new(YELLOW, BLACK),
+    );
+
     let mut item_query = <(&Item, &Name, &Carried)>::query();
     let mut y = 3;
     item_query
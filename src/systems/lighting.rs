@@ -0,0 +1,18 @@
+use crate::prelude::*;
+
+#[system]
+#[allow(clippy::borrowed_box)]
+#[read_component(Point)]
+#[read_component(LightSource)]
+pub fn lighting(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] light_map: &mut LightMap,
+) {
+    let sources: Vec<(Point, LightSource)> = <(&Point, &LightSource)>::query()
+        .iter(ecs)
+        .map(|(pos, light)| (*pos, *light))
+        .collect();
+    light_map.update(map, theme.ambient_light(), &sources);
+}
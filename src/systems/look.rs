@@ -0,0 +1,214 @@
+use legion::systems::CommandBuffer;
+
+use crate::prelude::*;
+
+const PANEL_WIDTH: i32 = 70;
+const PANEL_HEIGHT: i32 = 24;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Looking)]
+#[read_component(FieldOfView)]
+#[read_component(Name)]
+#[read_component(Health)]
+#[read_component(Damage)]
+#[read_component(Enemy)]
+#[read_component(Item)]
+#[read_component(Weapon)]
+#[read_component(ProvidesHealing)]
+#[read_component(ProvidesDungeonMap)]
+#[read_component(Description)]
+#[read_component(Carried)]
+#[read_component(Hidden)]
+#[allow(clippy::borrowed_box)]
+pub fn look(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] entity_memory: &EntityMemory,
+) {
+    let (player, looking, fov) = match <(Entity, &Looking, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    {
+        Some((player, looking, fov)) => (*player, *looking, fov),
+        None => return,
+    };
+
+    let mut cursor = looking.cursor;
+    if let Some(key) = *key {
+        let delta = match key {
+            VirtualKeyCode::Left => Point::new(-1, 0),
+            VirtualKeyCode::Right => Point::new(1, 0),
+            VirtualKeyCode::Up => Point::new(0, -1),
+            VirtualKeyCode::Down => Point::new(0, 1),
+            VirtualKeyCode::Escape | VirtualKeyCode::L => {
+                commands.remove_component::<Looking>(player);
+                return;
+            }
+            _ => Point::zero(),
+        };
+        let moved = cursor + delta;
+        if map.in_bounds(moved)
+            && moved.x >= camera.left_x
+            && moved.x < camera.right_x
+            && moved.y >= camera.top_y
+            && moved.y < camera.bottom_y
+        {
+            cursor = moved;
+            commands.add_component(player, Looking { cursor });
+        }
+    }
+
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(0);
+    draw_batch.set_bg(cursor - offset, GOLD);
+    draw_batch.submit(1000).expect("Batch error");
+
+    let lines = describe_tile(cursor, player, fov, ecs, map, theme.as_ref(), entity_memory);
+
+    let panel = Rect::with_size(
+        1,
+        SCREEN_HEIGHT * 2 - PANEL_HEIGHT - 1,
+        PANEL_WIDTH,
+        PANEL_HEIGHT,
+    );
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.fill_region(panel, ColorPair::new(BLACK, BLACK), 219);
+    draw_batch.draw_double_box(panel, ColorPair::new(WHITE, BLACK));
+    draw_batch.print_color(
+        Point::new(panel.x1 + 2, panel.y1),
+        " Look: cursor keys to move, Escape to finish ",
+        ColorPair::new(YELLOW, BLACK),
+    );
+    lines
+        .iter()
+        .take((PANEL_HEIGHT - 2) as usize)
+        .enumerate()
+        .for_each(|(y, (text, color))| {
+            draw_batch.print_color(
+                Point::new(panel.x1 + 2, panel.y1 + 2 + y as i32),
+                text,
+                ColorPair::new(*color, BLACK),
+            );
+        });
+    draw_batch.submit(10200).expect("Batch error");
+}
+
+fn describe_tile(
+    pos: Point,
+    player: Entity,
+    fov: &FieldOfView,
+    ecs: &SubWorld,
+    map: &Map,
+    theme: &dyn MapTheme,
+    entity_memory: &EntityMemory,
+) -> Vec<(String, (u8, u8, u8))> {
+    let mut lines = Vec::new();
+    let idx = map.point2d_to_index(pos);
+    let visible = fov.visible_tiles.contains(&pos);
+    if !visible && !map.revealed_tiles[idx] {
+        lines.push(("You haven't explored there yet.".to_string(), GRAY));
+        return lines;
+    }
+
+    let tile = theme.tile_description(map.tiles[idx]).to_string();
+    if visible {
+        lines.push((tile, WHITE));
+    } else {
+        lines.push((format!("{} (remembered, out of sight)", tile), GRAY));
+        entity_memory.remembered_at(pos).for_each(|memory| {
+            lines.push((String::new(), WHITE));
+            lines.push((format!("{} (last seen here)", memory.name), GRAY));
+        });
+        return lines;
+    }
+
+    <(Entity, &Point, &Name)>::query()
+        .filter(!component::<Hidden>())
+        .iter(ecs)
+        .filter(|(_, entity_pos, _)| **entity_pos == pos)
+        .for_each(|(entity, _, name)| {
+            lines.push((String::new(), WHITE));
+            if *entity == player {
+                lines.push(("That's you.".to_string(), CYAN));
+                return;
+            }
+            lines.append(&mut describe_entity(*entity, name, ecs));
+        });
+
+    lines
+}
+
+fn describe_entity(entity: Entity, name: &Name, ecs: &SubWorld) -> Vec<(String, (u8, u8, u8))> {
+    let mut lines = Vec::new();
+    let entry = ecs.entry_ref(entity).unwrap();
+    let is_enemy = entry.get_component::<Enemy>().is_ok();
+    lines.push((name.0.clone(), if is_enemy { RED } else { YELLOW }));
+
+    if let Ok(health) = entry.get_component::<Health>() {
+        lines.push((format!("  HP: {} / {}", health.current, health.max), WHITE));
+    }
+    if let Ok(damage) = entry.get_component::<Damage>() {
+        if entry.get_component::<Weapon>().is_ok() {
+            lines.push((
+                format!("  Weapon: +{} damage when wielded", damage.0),
+                WHITE,
+            ));
+        } else {
+            lines.push((format!("  Damage: {}", damage.0), WHITE));
+        }
+    }
+    if let Ok(healing) = entry.get_component::<ProvidesHealing>() {
+        lines.push((
+            format!("  Restores {} health when used", healing.amount),
+            WHITE,
+        ));
+    }
+    if entry.get_component::<ProvidesDungeonMap>().is_ok() {
+        lines.push((
+            "  Reveals the layout of this level when read".to_string(),
+            WHITE,
+        ));
+    }
+    let carried: Vec<String> = <(&Carried, &Name)>::query()
+        .iter(ecs)
+        .filter(|(carried, _)| carried.0 == entity)
+        .map(|(_, name)| name.0.clone())
+        .collect();
+    if !carried.is_empty() {
+        lines.push((format!("  Carrying: {}", carried.join(", ")), WHITE));
+    }
+    if let Ok(description) = entry.get_component::<Description>() {
+        wrap(&description.0, (PANEL_WIDTH - 6) as usize)
+            .into_iter()
+            .for_each(|line| lines.push((format!("  {}", line), GRAY)));
+    }
+    lines
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + word.len() + 1 > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
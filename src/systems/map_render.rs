@@ -1,32 +1,45 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::borrowed_box)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
+#[read_component(Travelling)]
 pub fn map_render(
     ecs: &SubWorld,
     #[resource] map: &Map,
     #[resource] camera: &Camera,
     #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] light_map: &LightMap,
 ) {
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
-    let player_fov = fov.iter(ecs).nth(0).unwrap();
+    let player_fov = fov.iter(ecs).next().unwrap();
+    let travel_path: Vec<Point> = <&Travelling>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .flat_map(|travelling| travelling.path.clone())
+        .collect();
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(0);
     for y in camera.top_y..=camera.bottom_y {
         for x in camera.left_x..camera.right_x {
             let pt = Point::new(x, y);
             let offset = Point::new(camera.left_x, camera.top_y);
-            let idx = map_idx(x, y);
+            let idx = map.map_idx(x, y);
             if map.in_bounds(pt) && player_fov.visible_tiles.contains(&pt) | map.revealed_tiles[idx]
             {
                 let tint = if player_fov.visible_tiles.contains(&pt) {
-                    WHITE
+                    light_map.apply(pt, theme.tile_tint(map.tiles[idx]))
                 } else {
-                    DARK_GRAY
+                    theme.remembered_tint()
                 };
                 let glyph = theme.tile_to_render(map.tiles[idx]);
-                draw_batch.set(pt - offset, ColorPair::new(tint, BLACK), glyph);
+                let background = if travel_path.contains(&pt) {
+                    NAVY
+                } else {
+                    BLACK
+                };
+                draw_batch.set(pt - offset, ColorPair::new(tint, background), glyph);
             }
         }
     }
@@ -0,0 +1,31 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Render)]
+#[read_component(Name)]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+#[read_component(Hidden)]
+pub fn memory(ecs: &SubWorld, #[resource] entity_memory: &mut EntityMemory) {
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let player_fov = fov.iter(ecs).next().unwrap();
+
+    let seen = <(Entity, &Point, &Render, &Name)>::query()
+        .filter(!component::<Player>() & !component::<Hidden>())
+        .iter(ecs)
+        .filter(|(_, pos, _, _)| player_fov.visible_tiles.contains(pos))
+        .map(|(entity, pos, render, name)| {
+            (
+                *entity,
+                RememberedEntity {
+                    position: *pos,
+                    glyph: render.glyph,
+                    name: name.0.clone(),
+                },
+            )
+        })
+        .collect();
+
+    entity_memory.update(&player_fov.visible_tiles, seen);
+}
@@ -1,14 +1,26 @@
-mod chasing;
+mod ai;
+mod auto_actions;
+mod boss;
+mod breath_attack;
 mod combat;
 mod end_turn;
 mod entity_render;
 mod fov;
 mod hud;
+mod lighting;
+mod look;
 mod map_render;
+mod memory;
+mod monster_pickup;
 mod movement;
+mod perception;
 mod player_input;
-mod random_move;
+mod poison;
+mod regeneration;
+mod summon_minions;
+mod terrain;
 mod tooltips;
+mod traps;
 mod use_items;
 
 use crate::prelude::*;
@@ -16,8 +28,12 @@ use crate::prelude::*;
 pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
         .add_system(player_input::player_input_system())
+        .add_system(auto_actions::auto_actions_system())
+        .add_system(look::look_system())
         .add_system(fov::fov_system())
         .flush()
+        .add_system(memory::memory_system())
+        .add_system(lighting::lighting_system())
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
@@ -32,8 +48,13 @@ pub fn build_player_scheduler() -> Schedule {
         .flush()
         .add_system(movement::movement_system())
         .flush()
+        .add_system(traps::traps_system())
+        .flush()
         .add_system(fov::fov_system())
         .flush()
+        .add_system(perception::perception_system())
+        .add_system(memory::memory_system())
+        .add_system(lighting::lighting_system())
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
@@ -43,19 +64,42 @@ pub fn build_player_scheduler() -> Schedule {
 
 pub fn build_monster_scheduler() -> Schedule {
     Schedule::builder()
-        .add_system(random_move::random_move_system())
-        .add_system(chasing::chasing_system())
+        .add_system(boss::boss_system())
+        .add_system(poison::poison_system())
+        .add_system(regeneration::regeneration_system())
+        .add_system(breath_attack::breath_attack_system())
+        .add_system(summon_minions::summon_minions_system())
+        .add_system(ai::ai_system())
         .flush()
         .add_system(use_items::use_items_system())
         .add_system(combat::combat_system())
         .flush()
         .add_system(movement::movement_system())
         .flush()
+        .add_system(monster_pickup::monster_pickup_system())
+        .add_system(terrain::terrain_system())
+        .flush()
         .add_system(fov::fov_system())
         .flush()
+        .add_system(memory::memory_system())
+        .add_system(lighting::lighting_system())
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
         .add_system(end_turn::end_turn_system())
         .build()
 }
+
+/// The decision-making and movement half of a monster turn, without any of the
+/// rendering. Used to measure how expensive monster turns are.
+pub fn build_monster_ai_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(ai::ai_system())
+        .flush()
+        .add_system(combat::combat_system())
+        .flush()
+        .add_system(movement::movement_system())
+        .flush()
+        .add_system(fov::fov_system())
+        .build()
+}
@@ -0,0 +1,50 @@
+use legion::systems::CommandBuffer;
+
+use crate::prelude::*;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Enemy)]
+#[read_component(Item)]
+#[read_component(Weapon)]
+#[read_component(Damage)]
+#[read_component(ProvidesHealing)]
+#[read_component(Carried)]
+pub fn monster_pickup(ecs: &SubWorld, commands: &mut CommandBuffer) {
+    let mut monsters = <(Entity, &Point)>::query().filter(component::<Enemy>());
+    let mut items = <(Entity, &Point)>::query().filter(component::<Item>());
+
+    monsters.iter(ecs).for_each(|(monster, pos)| {
+        items
+            .iter(ecs)
+            .filter(|(_, item_pos)| *item_pos == pos)
+            .for_each(|(item, _)| {
+                let entry = ecs.entry_ref(*item).unwrap();
+                if entry.get_component::<ProvidesHealing>().is_ok() {
+                    pick_up(*item, *monster, commands);
+                } else if entry.get_component::<Weapon>().is_ok() {
+                    let damage = entry.get_component::<Damage>().map(|d| d.0).unwrap_or(0);
+                    let wielded = <(Entity, &Carried, &Damage)>::query()
+                        .filter(component::<Weapon>())
+                        .iter(ecs)
+                        .find(|(_, carried, _)| carried.0 == *monster)
+                        .map(|(weapon, _, weapon_damage)| (*weapon, weapon_damage.0));
+
+                    match wielded {
+                        Some((_, wielded_damage)) if wielded_damage >= damage => {}
+                        Some((old_weapon, _)) => {
+                            commands.remove_component::<Carried>(old_weapon);
+                            commands.add_component(old_weapon, *pos);
+                            pick_up(*item, *monster, commands);
+                        }
+                        None => pick_up(*item, *monster, commands),
+                    }
+                }
+            });
+    });
+}
+
+fn pick_up(item: Entity, monster: Entity, commands: &mut CommandBuffer) {
+    commands.remove_component::<Point>(item);
+    commands.add_component(item, Carried(monster));
+}
@@ -5,6 +5,12 @@ use crate::prelude::*;
 #[system(for_each)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
+#[read_component(Carried)]
+#[read_component(Key)]
+#[read_component(Point)]
+#[read_component(Trap)]
+#[read_component(Slowed)]
+#[read_component(Swimmer)]
 pub fn movement(
     entity: &Entity,
     want_move: &WantsToMove,
@@ -13,8 +19,24 @@ pub fn movement(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
 ) {
-    if map.can_enter_tile(want_move.destination) {
+    let (slowed, swims) = match ecs.entry_ref(want_move.entity) {
+        Ok(entry) => (
+            entry.get_component::<Slowed>().is_ok(),
+            entry.get_component::<Swimmer>().is_ok(),
+        ),
+        Err(_) => (false, false),
+    };
+    let destination_tile = map.try_idx(want_move.destination).map(|idx| map.tiles[idx]);
+
+    if slowed {
+        commands.remove_component::<Slowed>(want_move.entity);
+    } else if map.can_enter_tile(want_move.destination)
+        || swims && destination_tile == Some(TileType::DeepWater)
+    {
         commands.add_component(want_move.entity, want_move.destination);
+        if destination_tile == Some(TileType::ShallowWater) {
+            commands.add_component(want_move.entity, Slowed);
+        }
 
         if let Ok(entry) = ecs.entry_ref(want_move.entity) {
             if let Ok(fov) = entry.get_component::<FieldOfView>() {
@@ -22,12 +44,52 @@ pub fn movement(
 
                 if entry.get_component::<Player>().is_ok() {
                     camera.on_player_move(want_move.destination);
-                    fov.visible_tiles
-                        .iter()
-                        .for_each(|pos| map.revealed_tiles[map_idx(pos.x, pos.y)] = true)
+                    fov.visible_tiles.iter().for_each(|pos| {
+                        let idx = map.map_idx(pos.x, pos.y);
+                        map.revealed_tiles[idx] = true;
+                    });
+                    <(Entity, &Point)>::query()
+                        .filter(component::<Trap>())
+                        .iter(ecs)
+                        .filter(|(_, pos)| **pos == want_move.destination)
+                        .for_each(|(trap, _)| {
+                            commands.push((
+                                (),
+                                SprungTrap {
+                                    victim: want_move.entity,
+                                    trap: *trap,
+                                },
+                            ));
+                        });
                 }
             }
         }
+    } else if let Some(idx) = map.try_idx(want_move.destination) {
+        let opened = match map.tiles[idx] {
+            TileType::Door => true,
+            TileType::LockedDoor => match carried_key(want_move.entity, ecs) {
+                Some(key) => {
+                    commands.remove(key);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        };
+        if opened {
+            map.set_tile(idx, TileType::OpenDoor);
+            <(Entity, &FieldOfView)>::query()
+                .iter(ecs)
+                .for_each(|(entity, fov)| commands.add_component(*entity, fov.clone_dirty()));
+        }
     }
     commands.remove(*entity);
 }
+
+fn carried_key(owner: Entity, ecs: &SubWorld) -> Option<Entity> {
+    <(Entity, &Carried)>::query()
+        .filter(component::<Key>())
+        .iter(ecs)
+        .find(|(_, carried)| carried.0 == owner)
+        .map(|(key, _)| *key)
+}
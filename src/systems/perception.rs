@@ -0,0 +1,63 @@
+use legion::systems::CommandBuffer;
+
+use crate::prelude::*;
+
+/// How close a hidden trap has to be before the player might notice it.
+const PASSIVE_RADIUS: f32 = 2.0;
+/// Percentage chance, each turn, of noticing a nearby hidden trap.
+const PASSIVE_CHANCE: i32 = 10;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+pub fn perception(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] rng: &mut RandomNumberGenerator,
+) {
+    let (player_pos, fov) = match <(&Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+    {
+        Some((pos, fov)) => (*pos, fov),
+        None => return,
+    };
+    reveal_nearby_traps(
+        player_pos,
+        fov,
+        PASSIVE_RADIUS,
+        PASSIVE_CHANCE,
+        ecs,
+        rng,
+        commands,
+    );
+}
+
+/// Gives every hidden trap within `radius` of the player, and in sight, a
+/// `chance` percent chance of being found.
+pub fn reveal_nearby_traps(
+    player_pos: Point,
+    fov: &FieldOfView,
+    radius: f32,
+    chance: i32,
+    ecs: &SubWorld,
+    rng: &mut RandomNumberGenerator,
+    commands: &mut CommandBuffer,
+) {
+    <(Entity, &Point)>::query()
+        .filter(component::<Trap>() & component::<Hidden>())
+        .iter(ecs)
+        .filter(|(_, pos)| {
+            fov.visible_tiles.contains(pos)
+                && DistanceAlg::Pythagoras.distance2d(player_pos, **pos) <= radius
+        })
+        .for_each(|(trap, _)| {
+            if rng.range(0, 100) < chance {
+                commands.remove_component::<Hidden>(*trap);
+            }
+        });
+}
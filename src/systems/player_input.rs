@@ -1,7 +1,16 @@
 use legion::{systems::CommandBuffer, world::SubWorld};
 
+use super::auto_actions::{
+    is_auto_acting, rest, start_exploring, start_travelling, stop_auto_actions,
+};
+use super::perception::reveal_nearby_traps;
 use crate::{components::Player, prelude::*};
 
+/// How far a deliberate search for traps reaches.
+const SEARCH_RADIUS: f32 = 3.0;
+/// Percentage chance a search finds each hidden trap in reach.
+const SEARCH_CHANCE: i32 = 75;
+
 #[system]
 #[write_component(Point)]
 #[read_component(Player)]
@@ -10,23 +19,107 @@ use crate::{components::Player, prelude::*};
 #[read_component(Item)]
 #[read_component(Carried)]
 #[read_component(Weapon)]
+#[read_component(FieldOfView)]
+#[read_component(Resting)]
+#[read_component(AutoExploring)]
+#[read_component(Travelling)]
+#[read_component(Looking)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] mouse_pos: &Point,
+    #[resource] mouse_click: &MouseClick,
+    #[resource] camera: &Camera,
+    #[resource] map: &Map,
+    #[resource] rng: &mut RandomNumberGenerator,
     #[resource] turn_state: &mut TurnState,
 ) {
     let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
+    let looking = <&Looking>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .is_some();
+    if looking {
+        return;
+    }
+
+    if key.is_none() && mouse_click.0 {
+        let player = players
+            .iter(ecs)
+            .map(|(entity, _)| *entity)
+            .next()
+            .unwrap();
+        let target = *mouse_pos + Point::new(camera.left_x, camera.top_y);
+        stop_auto_actions(player, commands);
+        start_travelling(player, target, ecs, map, commands);
+        return;
+    }
+
     if let Some(key) = *key {
+        let player = players
+            .iter(ecs)
+            .map(|(entity, _)| *entity)
+            .next()
+            .unwrap();
+        if is_auto_acting(player, ecs) {
+            stop_auto_actions(player, commands);
+            return;
+        }
+
         let delta = match key {
             VirtualKeyCode::Left => Point::new(-1, 0),
             VirtualKeyCode::Right => Point::new(1, 0),
             VirtualKeyCode::Up => Point::new(0, -1),
             VirtualKeyCode::Down => Point::new(0, 1),
+            VirtualKeyCode::Space => {
+                rest(player, ecs);
+                Point::new(0, 0)
+            }
+            VirtualKeyCode::R => {
+                commands.add_component(player, Resting);
+                return;
+            }
+            VirtualKeyCode::S => {
+                let (player_pos, fov) = <(&Point, &FieldOfView)>::query()
+                    .filter(component::<Player>())
+                    .iter(ecs)
+                    .map(|(pos, fov)| (*pos, fov.clone()))
+                    .next()
+                    .unwrap();
+                reveal_nearby_traps(
+                    player_pos,
+                    &fov,
+                    SEARCH_RADIUS,
+                    SEARCH_CHANCE,
+                    ecs,
+                    rng,
+                    commands,
+                );
+                Point::new(0, 0)
+            }
+            VirtualKeyCode::O => {
+                start_exploring(player, ecs, commands);
+                return;
+            }
+            VirtualKeyCode::L => {
+                let cursor = players
+                    .iter(ecs)
+                    .map(|(_, pos)| *pos)
+                    .next()
+                    .unwrap();
+                commands.add_component(player, Looking { cursor });
+                return;
+            }
             VirtualKeyCode::G => {
-                let (player, player_pos) = players
+                let player_pos = players
                     .iter(ecs)
-                    .find_map(|(entity, pos)| Some((*entity, *pos)))
+                    .map(|(_, pos)| *pos)
+                    .next()
                     .unwrap();
 
                 let mut items = <(Entity, &Item, &Point)>::query();
@@ -59,12 +152,13 @@ pub fn player_input(
             VirtualKeyCode::Key7 => use_item(6, ecs, commands),
             VirtualKeyCode::Key8 => use_item(7, ecs, commands),
             VirtualKeyCode::Key9 => use_item(8, ecs, commands),
-            _ => Point::new(0, 0),
+            _ => return,
         };
 
         let (player_entity, destination) = players
             .iter(ecs)
-            .find_map(|(entity, pos)| Some((*entity, *pos + delta)))
+            .map(|(entity, pos)| (*entity, *pos + delta))
+            .next()
             .unwrap();
 
         let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
@@ -103,14 +197,16 @@ pub fn player_input(
 fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point {
     let player_entity = <(Entity, &Player)>::query()
         .iter(ecs)
-        .find_map(|(entity, _player)| Some(*entity))
+        .map(|(entity, _player)| *entity)
+        .next()
         .unwrap();
     let item_entity = <(Entity, &Item, &Carried)>::query()
         .iter(ecs)
         .filter(|(_, _, carried)| carried.0 == player_entity)
         .enumerate()
         .filter(|(item_count, (_, _, _))| *item_count == n)
-        .find_map(|(_, (item_entity, _, _))| Some(*item_entity));
+        .map(|(_, (item_entity, _, _))| *item_entity)
+        .next();
 
     if let Some(item_entity) = item_entity {
         commands.push((
@@ -0,0 +1,53 @@
+use legion::systems::CommandBuffer;
+
+use super::{auto_actions::stop_auto_actions, combat::kill_monster};
+use crate::{
+    prelude::*,
+    score_tracker::ScoreTracker,
+    spawner::template::{Spawner, TemplateSpawner},
+};
+
+#[system]
+#[read_component(Player)]
+#[write_component(Poisoned)]
+#[write_component(Health)]
+#[read_component(Point)]
+#[read_component(Carried)]
+#[read_component(LootTable)]
+#[read_component(SplitsOnDeath)]
+pub fn poison(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] score_tracker: &mut ScoreTracker,
+    #[resource] templates: &TemplateSpawner<Spawner>,
+    #[resource] rng: &mut RandomNumberGenerator,
+) {
+    let mut poisoned = <(Entity, &mut Poisoned, &mut Health)>::query();
+    let players: Vec<Entity> = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    let mut dead = Vec::new();
+    poisoned
+        .iter_mut(ecs)
+        .for_each(|(entity, poison, health)| {
+            health.current -= poison.damage;
+            poison.turns -= 1;
+            if poison.turns < 1 {
+                commands.remove_component::<Poisoned>(*entity);
+            }
+
+            if players.contains(entity) {
+                stop_auto_actions(*entity, commands);
+            } else if health.current < 1 {
+                dead.push(*entity);
+            }
+        });
+
+    dead.into_iter().for_each(|victim| {
+        kill_monster(victim, ecs, map, score_tracker, templates, rng, commands);
+    });
+}
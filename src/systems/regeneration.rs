@@ -0,0 +1,12 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(Regenerates)]
+#[write_component(Health)]
+pub fn regeneration(ecs: &mut SubWorld) {
+    <(&Regenerates, &mut Health)>::query()
+        .iter_mut(ecs)
+        .for_each(|(regenerates, health)| {
+            health.current = i32::min(health.max, health.current + regenerates.0);
+        });
+}
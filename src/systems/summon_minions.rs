@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+
+use legion::systems::CommandBuffer;
+
+use crate::{
+    prelude::*,
+    spawner::template::{free_tiles_around, Spawner, TemplateSpawner},
+};
+
+/// Calls minions to a monster's side once it has spotted the player, then
+/// waits out its cooldown before calling again. It stops calling while it
+/// already has as many living minions as it is allowed.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Health)]
+#[read_component(FieldOfView)]
+#[write_component(SummonsMinions)]
+pub fn summon_minions(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] templates: &TemplateSpawner<Spawner>,
+) {
+    let player_pos = <&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .copied()
+        .next()
+        .unwrap();
+    let mut taken: HashSet<Point> = <&Point>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .copied()
+        .collect();
+    let living: HashSet<Entity> = <Entity>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    <(&Point, &FieldOfView, &mut SummonsMinions)>::query()
+        .iter_mut(ecs)
+        .for_each(|(pos, fov, summons)| {
+            summons.minions.retain(|minion| living.contains(minion));
+            let room = (summons.limit - summons.minions.len() as i32).min(summons.count);
+            if summons.ready_in > 0 {
+                summons.ready_in -= 1;
+            } else if room > 0 && fov.visible_tiles.contains(&player_pos) {
+                free_tiles_around(*pos, room as usize, map, &mut taken)
+                    .into_iter()
+                    .filter_map(|point| templates.spawn_named(&summons.minion, point, commands))
+                    .for_each(|minion| summons.minions.push(minion));
+                summons.ready_in = summons.cooldown;
+            }
+        });
+}
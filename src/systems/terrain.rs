@@ -0,0 +1,57 @@
+use legion::systems::CommandBuffer;
+
+use super::{auto_actions::stop_auto_actions, combat::kill_monster};
+use crate::{
+    prelude::*,
+    score_tracker::ScoreTracker,
+    spawner::template::{Spawner, TemplateSpawner},
+};
+
+const LAVA_DAMAGE: i32 = 3;
+
+/// Applies the effects of whatever tile everything is standing on once the
+/// turn is over.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[write_component(Health)]
+#[read_component(Carried)]
+#[read_component(LootTable)]
+#[read_component(SplitsOnDeath)]
+pub fn terrain(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] score_tracker: &mut ScoreTracker,
+    #[resource] templates: &TemplateSpawner<Spawner>,
+    #[resource] rng: &mut RandomNumberGenerator,
+) {
+    let players: Vec<Entity> = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    let mut dead = Vec::new();
+    <(Entity, &Point, &mut Health)>::query()
+        .iter_mut(ecs)
+        .filter(|(_, pos, _)| map.tiles[map.map_idx(pos.x, pos.y)] == TileType::Lava)
+        .for_each(|(entity, _, health)| {
+            health.current -= LAVA_DAMAGE;
+            if players.contains(entity) {
+                stop_auto_actions(*entity, commands);
+                commands.push((
+                    (),
+                    ScreenEffects {
+                        effect: ScreenEffectsEnum::TakeDamage,
+                    },
+                ));
+            } else if health.current < 1 {
+                dead.push(*entity);
+            }
+        });
+
+    dead.into_iter().for_each(|victim| {
+        kill_monster(victim, ecs, map, score_tracker, templates, rng, commands);
+    });
+}
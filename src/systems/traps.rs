@@ -0,0 +1,113 @@
+use legion::systems::CommandBuffer;
+
+use super::auto_actions::stop_auto_actions;
+use crate::prelude::*;
+
+#[system]
+#[read_component(SprungTrap)]
+#[read_component(Trap)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Enemy)]
+#[write_component(Point)]
+#[write_component(Health)]
+#[write_component(Brain)]
+pub fn traps(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] camera: &mut Camera,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] turn_state: &mut TurnState,
+) {
+    let sprung: Vec<(Entity, Entity, Entity)> = <(Entity, &SprungTrap)>::query()
+        .iter(ecs)
+        .map(|(message, sprung)| (*message, sprung.victim, sprung.trap))
+        .collect();
+
+    sprung.iter().for_each(|(message, victim, trap)| {
+        commands.remove(*message);
+        let effect = match ecs.entry_ref(*trap) {
+            Ok(entry) => match entry.get_component::<Trap>() {
+                Ok(trap) => trap.effect,
+                Err(_) => return,
+            },
+            Err(_) => return,
+        };
+        commands.remove_component::<Hidden>(*trap);
+        stop_auto_actions(*victim, commands);
+
+        let (victim_pos, map_level) = {
+            let entry = ecs.entry_ref(*victim).unwrap();
+            let map_level = entry
+                .get_component::<Player>()
+                .map(|player| player.map_level)
+                .unwrap_or(0);
+            (*entry.get_component::<Point>().unwrap(), map_level)
+        };
+
+        match effect {
+            TrapEffect::Spikes { damage } => {
+                if let Ok(health) = ecs
+                    .entry_mut(*victim)
+                    .unwrap()
+                    .get_component_mut::<Health>()
+                {
+                    health.current -= damage;
+                }
+                commands.push((
+                    (),
+                    ScreenEffects {
+                        effect: ScreenEffectsEnum::TakeDamage,
+                    },
+                ));
+            }
+            TrapEffect::Teleport => {
+                if let Some(destination) = random_free_tile(map, ecs, rng) {
+                    commands.add_component(*victim, destination);
+                    camera.on_player_move(destination);
+                    if let Ok(fov) = ecs
+                        .entry_ref(*victim)
+                        .unwrap()
+                        .get_component::<FieldOfView>()
+                    {
+                        commands.add_component(*victim, fov.clone_dirty());
+                    }
+                }
+            }
+            TrapEffect::Alarm { radius } => {
+                <(&Point, &mut Brain)>::query()
+                    .filter(component::<Enemy>())
+                    .iter_mut(ecs)
+                    .filter(|(pos, _)| {
+                        DistanceAlg::Pythagoras.distance2d(**pos, victim_pos) <= radius as f32
+                    })
+                    .for_each(|(_, brain)| {
+                        brain.state = AiState::Hunting {
+                            last_known: victim_pos,
+                        };
+                    });
+            }
+            // There is nowhere further down from the last level.
+            TrapEffect::Pit if map_level < 2 => *turn_state = TurnState::NextLevel,
+            TrapEffect::Pit => {}
+        }
+    });
+}
+
+fn random_free_tile(map: &Map, ecs: &SubWorld, rng: &mut RandomNumberGenerator) -> Option<Point> {
+    let occupied: Vec<Point> = <&Point>::query()
+        .filter(component::<Health>())
+        .iter(ecs)
+        .copied()
+        .collect();
+    let free: Vec<Point> = map
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| **t == TileType::Floor)
+        .map(|(idx, _)| map.index_to_point2d(idx))
+        .filter(|pt| !occupied.contains(pt))
+        .collect();
+    rng.random_slice_entry(&free).copied()
+}
@@ -7,12 +7,19 @@ use crate::prelude::*;
 #[read_component(ProvidesHealing)]
 #[write_component(Health)]
 #[read_component(ProvidesDungeonMap)]
+#[read_component(Key)]
 pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
     <(Entity, &ActivateItem)>::query()
         .iter(ecs)
         .for_each(|(entity, activate)| {
             let item = ecs.entry_ref(activate.item);
+            // Keys are only used up by opening a locked door.
+            let is_key = matches!(&item, Ok(item) if item.get_component::<Key>().is_ok());
+            if is_key {
+                commands.remove(*entity);
+                return;
+            }
             if let Ok(item) = item {
                 if let Ok(healing) = item.get_component::<ProvidesHealing>() {
                     healing_to_apply.push((activate.used_by, healing.amount));
@@ -34,3 +41,35 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_keep_a_carried_key_when_it_is_activated() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        let player = ecs.push((Player { map_level: 0 }, Point::new(1, 1)));
+        let key = ecs.push((Item, Key, Carried(player)));
+        ecs.push((
+            (),
+            ActivateItem {
+                used_by: player,
+                item: key,
+            },
+        ));
+
+        Schedule::builder()
+            .add_system(use_items_system())
+            .build()
+            .execute(&mut ecs, &mut resources);
+
+        let owner = ecs
+            .entry(key)
+            .map(|entry| entry.get_component::<Carried>().unwrap().0);
+        assert_eq!(owner, Some(player));
+        assert_eq!(<&ActivateItem>::query().iter(&ecs).count(), 0);
+    }
+}
@@ -1,9 +1,11 @@
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TurnState {
+    StartScreen,
     AwaitingInput,
     PlayerTurn,
     MonsterTurn,
     GameOver,
     Victory,
     NextLevel,
+    ShowingMapGen,
 }